@@ -0,0 +1,259 @@
+//! Build script for Smile
+//!
+//! Generates the static emoji tables from the vendored Unicode data in `data/`,
+//! with perfect hash indexes for the lookups and the names and keywords in
+//! lowercase for the search. The generated code is written to `$OUT_DIR/emoji_data.rs`,
+//! `$OUT_DIR/annotations_data.rs` and `$OUT_DIR/symbols_data.rs` and included
+//! by `src/emoticons.rs`, `src/annotations.rs` and `src/symbols.rs`. The
+//! emoji and annotation parsers live in `src/data_files.rs`, which
+//! `smile update-data` uses as well.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[path = "src/data_files.rs"]
+mod data_files;
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+
+    let emoji_test = Path::new(&manifest_dir).join("data").join("emoji-test.txt");
+    println!("cargo:rerun-if-changed={}", emoji_test.display());
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/data_files.rs");
+
+    let content = fs::read_to_string(&emoji_test)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", emoji_test.display(), e));
+    let entries = data_files::parse_emoji_test("emoji-test.txt", &content).unwrap_or_else(|e| panic!("{}", e));
+
+    let mut code = String::new();
+    code.push_str("// Generated by build.rs from data/emoji-test.txt - do not edit\n\n");
+    code.push_str("/// All entries of emoji-test.txt in CLDR order\n");
+    code.push_str("pub static EMOJI_DATA: &[EmojiEntry] = &[\n");
+    let mut index = phf_codegen::Map::new();
+    for (position, entry) in entries.iter().enumerate() {
+        let emoji: String = entry
+            .codepoints
+            .iter()
+            .map(|cp| format!("\\u{{{:X}}}", cp))
+            .collect();
+        let codepoints: Vec<String> = entry.codepoints.iter().map(|cp| format!("0x{:X}", cp)).collect();
+        writeln!(
+            code,
+            "    EmojiEntry {{ emoji: \"{}\", codepoints: &[{}], group: {:?}, subgroup: {:?}, name: {:?}, search_name: {:?}, status: Qualification::{}, version: EmojiVersion {{ major: {}, minor: {} }} }},",
+            emoji,
+            codepoints.join(", "),
+            entry.group,
+            entry.subgroup,
+            entry.name,
+            entry.name.to_lowercase(),
+            entry.status,
+            entry.major,
+            entry.minor,
+        )
+        .unwrap();
+        index.entry(sequence(&entry.codepoints), &position.to_string());
+    }
+    code.push_str("];\n\n");
+    code.push_str("/// Position of each emoji in `EMOJI_DATA`\n");
+    writeln!(code, "pub static EMOJI_INDEX: phf::Map<&'static str, usize> = {};", index.build()).unwrap();
+
+    fs::write(Path::new(&out_dir).join("emoji_data.rs"), code).expect("Failed to write emoji_data.rs");
+
+    let data_dir = Path::new(&manifest_dir).join("data");
+    generate_annotations(
+        &[data_dir.join("annotations"), data_dir.join("annotations-derived")],
+        &entries,
+        Path::new(&out_dir),
+    );
+    let emoji_version = header_value(&content, "# Version: ").unwrap_or_default();
+    generate_symbols(&data_dir, emoji_version, Path::new(&out_dir));
+}
+
+/// Value of a header line of a Unicode data file, e.g. "16.0" for "# Version: 16.0"
+fn header_value<'a>(content: &'a str, prefix: &str) -> Option<&'a str> {
+    content
+        .lines()
+        .take_while(|line| line.starts_with('#'))
+        .find_map(|line| line.strip_prefix(prefix))
+        .map(str::trim)
+}
+
+/// The emoji of a code point sequence
+fn sequence(codepoints: &[u32]) -> String {
+    codepoints.iter().filter_map(|&cp| char::from_u32(cp)).collect()
+}
+
+/// Generate the character and block tables of the symbol browser from
+/// `data/UnicodeData.txt` and `data/Blocks.txt`
+///
+/// Warns when Blocks.txt is of another Unicode release than the emoji data,
+/// as the symbol browser would miss the newer characters.
+fn generate_symbols(data_dir: &Path, emoji_version: &str, out_dir: &Path) {
+    let read = |name: &str| {
+        let path = data_dir.join(name);
+        println!("cargo:rerun-if-changed={}", path.display());
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e))
+    };
+    let characters = parse_unicode_data(&read("UnicodeData.txt"));
+    let blocks_content = read("Blocks.txt");
+    let blocks = parse_blocks(&blocks_content);
+
+    // "# Blocks-16.0.0.txt"
+    let unicode_version = header_value(&blocks_content, "# Blocks-").and_then(|v| v.strip_suffix(".txt"));
+    if !unicode_version.is_some_and(|v| v.starts_with(&format!("{}.", emoji_version))) {
+        println!(
+            "cargo:warning=data/Blocks.txt is Unicode {} but the emoji data is {}; run `make data`",
+            unicode_version.unwrap_or("unknown"),
+            emoji_version
+        );
+    }
+
+    let mut code = String::new();
+    code.push_str("// Generated by build.rs from data/UnicodeData.txt and data/Blocks.txt - do not edit\n\n");
+    code.push_str("/// Named characters of UnicodeData.txt in code point order\n");
+    code.push_str("pub static CHARACTERS: &[CharInfo] = &[\n");
+    for (codepoint, name, category) in &characters {
+        writeln!(
+            code,
+            "    CharInfo {{ ch: '\\u{{{:X}}}', name: {:?}, category: {:?} }},",
+            codepoint, name, category
+        )
+        .unwrap();
+    }
+    code.push_str("];\n\n");
+    code.push_str("/// Unicode blocks in code point order\n");
+    code.push_str("pub static BLOCKS: &[Block] = &[\n");
+    for (first, last, name) in &blocks {
+        writeln!(code, "    Block {{ first: 0x{:X}, last: 0x{:X}, name: {:?} }},", first, last, name).unwrap();
+    }
+    code.push_str("];\n");
+
+    fs::write(out_dir.join("symbols_data.rs"), code).expect("Failed to write symbols_data.rs");
+}
+
+/// Parse the UnicodeData.txt format into (code point, name, general category):
+///
+/// ```text
+/// 21D2;RIGHTWARDS DOUBLE ARROW;Sm;0;ON;;;;;N;RIGHT DOUBLE ARROW;;;;
+/// ```
+///
+/// Controls, surrogates, private use characters and the ranges of
+/// ideographs and syllables (`<CJK Ideograph, First>`) have no name of their
+/// own and are left out.
+fn parse_unicode_data(content: &str) -> Vec<(u32, String, String)> {
+    let mut characters = Vec::new();
+
+    for (line_no, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fail = |what: &str| -> ! { panic!("UnicodeData.txt:{}: {}", line_no + 1, what) };
+
+        let mut fields = line.split(';');
+        let codepoint = fields
+            .next()
+            .and_then(|cp| u32::from_str_radix(cp, 16).ok())
+            .unwrap_or_else(|| fail("invalid code point"));
+        let name = fields.next().unwrap_or_else(|| fail("missing name"));
+        let category = fields.next().unwrap_or_else(|| fail("missing general category"));
+
+        if name.starts_with('<') || matches!(category, "Cc" | "Cs" | "Co" | "Cn") {
+            continue;
+        }
+        characters.push((codepoint, name.to_string(), category.to_string()));
+    }
+
+    characters
+}
+
+/// Parse the Blocks.txt format:
+///
+/// ```text
+/// 2190..21FF; Arrows
+/// ```
+fn parse_blocks(content: &str) -> Vec<(u32, u32, String)> {
+    let mut blocks = Vec::new();
+
+    for (line_no, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fail = |what: &str| -> ! { panic!("Blocks.txt:{}: {}", line_no + 1, what) };
+
+        let (range, name) = line.split_once(';').unwrap_or_else(|| fail("missing block name"));
+        let (first, last) = range.split_once("..").unwrap_or_else(|| fail("invalid range"));
+        let parse = |cp: &str| u32::from_str_radix(cp.trim(), 16).unwrap_or_else(|_| fail("invalid code point"));
+        blocks.push((parse(first), parse(last), name.trim().to_string()));
+    }
+
+    blocks
+}
+
+/// Generate the annotation tables from the `<locale>.xml` files of the
+/// annotation directories
+///
+/// CLDR writes emoji without variation selectors; the index of a locale
+/// also has the forms with variation selectors of emoji-test.txt, so the
+/// emoji of the picker are found without stripping them first.
+fn generate_annotations(dirs: &[PathBuf], emoji: &[data_files::TestEntry], out_dir: &Path) {
+    for dir in dirs {
+        // Cargo watches every file of a directory
+        println!("cargo:rerun-if-changed={}", dir.display());
+    }
+    let locales = data_files::read_annotations(dirs).unwrap_or_else(|e| panic!("{}", e));
+
+    let mut code = String::new();
+    code.push_str("// Generated by build.rs from data/annotations*/*.xml - do not edit\n\n");
+    code.push_str("/// CLDR annotations per locale, in file order\n");
+    code.push_str("pub static ANNOTATION_DATA: &[(&str, &[Annotation])] = &[\n");
+    let mut locale_index = phf_codegen::Map::new();
+    for (locale_position, (locale, entries)) in locales.iter().enumerate() {
+        writeln!(code, "    ({:?}, &[", locale).unwrap();
+        let mut index = phf_codegen::Map::new();
+        let mut keys = HashSet::new();
+        for (position, entry) in entries.iter().enumerate() {
+            let search_terms: Vec<String> = std::iter::once(&entry.name)
+                .chain(&entry.keywords)
+                .map(|term| term.to_lowercase())
+                .collect();
+            writeln!(
+                code,
+                "        Annotation {{ emoji: {:?}, name: {:?}, keywords: &{:?}, search_terms: &{:?} }},",
+                entry.emoji, entry.name, entry.keywords, search_terms,
+            )
+            .unwrap();
+            if keys.insert(entry.emoji.clone()) {
+                index.entry(entry.emoji.clone(), &position.to_string());
+            }
+        }
+        code.push_str("    ]),\n");
+
+        let positions: HashMap<&str, usize> =
+            entries.iter().enumerate().map(|(position, entry)| (entry.emoji.as_str(), position)).collect();
+        for entry in emoji {
+            let qualified = sequence(&entry.codepoints);
+            let position = positions.get(qualified.replace('\u{FE0F}', "").as_str());
+            if let Some(position) = position.filter(|_| !keys.contains(&qualified)) {
+                index.entry(qualified.clone(), &position.to_string());
+                keys.insert(qualified);
+            }
+        }
+        locale_index.entry(locale.clone(), &format!("({}, {})", locale_position, index.build()));
+    }
+    code.push_str("];\n\n");
+    code.push_str("/// Position of each locale in `ANNOTATION_DATA` and of each emoji in its annotations\n");
+    writeln!(
+        code,
+        "pub static ANNOTATION_INDEX: phf::Map<&'static str, (usize, phf::Map<&'static str, usize>)> = {};",
+        locale_index.build()
+    )
+    .unwrap();
+
+    fs::write(out_dir.join("annotations_data.rs"), code).expect("Failed to write annotations_data.rs");
+}
@@ -0,0 +1,174 @@
+//! CLDR emoji annotations - names and keywords in several languages
+//!
+//! The annotations are generated at build time from the vendored CLDR
+//! files in `data/annotations/<locale>.xml` and, for the flags, in
+//! `data/annotations-derived/<locale>.xml` (see `build.rs`), with a perfect
+//! hash index per locale. The search locales come from the
+//! `search_locales` setting or the environment.
+
+use crate::emoticons;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Annotation of an emoji in one language
+#[derive(Debug)]
+pub struct Annotation {
+    /// The emoji, without variation selectors
+    pub emoji: &'static str,
+    /// Short name, e.g. "grinsendes Gesicht"
+    pub name: &'static str,
+    /// Search keywords, e.g. ["Gesicht", "grinsendes Gesicht", "lol"]
+    #[allow(dead_code)]
+    pub keywords: &'static [&'static str],
+    /// The name and the keywords in lowercase, for the search
+    pub search_terms: &'static [&'static str],
+}
+
+include!(concat!(env!("OUT_DIR"), "/annotations_data.rs"));
+
+/// Annotations of one locale
+pub type LocaleAnnotations = (&'static str, &'static [Annotation]);
+
+static CACHED_ANNOTATIONS: OnceLock<Vec<LocaleAnnotations>> = OnceLock::new();
+static CACHE_INDEX: OnceLock<HashMap<&'static str, HashMap<&'static str, &'static Annotation>>> = OnceLock::new();
+
+/// Use the annotations of the data cache of `smile update-data`
+///
+/// They take precedence over the built-in annotations of the same locales;
+/// emoji they have no annotation for, e.g. the flags when only CLDR's
+/// `annotations/` directory was compiled, keep the built-in ones.
+///
+/// Must be called before the first lookup; returns false if the built-in
+/// annotations are already in use.
+pub fn set_annotation_data(data: Vec<LocaleAnnotations>) -> bool {
+    CACHED_ANNOTATIONS.set(data).is_ok()
+}
+
+/// Look up the annotation of an emoji in a locale
+///
+/// Locales of the data cache are indexed once, at their first lookup; the
+/// built-in ones have a perfect hash index.
+pub fn get(locale: &str, emoji: &str) -> Option<&'static Annotation> {
+    // CLDR writes emoji without variation selectors
+    let stripped = || emoji.contains('\u{FE0F}').then(|| emoticons::without_variation_selectors(emoji));
+
+    let cached = CACHED_ANNOTATIONS.get_or_init(Vec::new);
+    let from_cache = || {
+        let index = CACHE_INDEX.get_or_init(|| {
+            cached
+                .iter()
+                .map(|(locale, annotations)| (*locale, annotations.iter().map(|a| (a.emoji, a)).collect()))
+                .collect()
+        });
+        let annotations = index.get(locale)?;
+        annotations
+            .get(emoji)
+            .or_else(|| annotations.get(stripped()?.as_ref()))
+            .copied()
+    };
+    let built_in = || {
+        let (locale_position, index) = ANNOTATION_INDEX.get(locale)?;
+        let position = index.get(emoji).or_else(|| index.get(stripped()?.as_ref()))?;
+        Some(&ANNOTATION_DATA[*locale_position].1[*position])
+    };
+
+    if cached.is_empty() {
+        built_in()
+    } else {
+        from_cache().or_else(built_in)
+    }
+}
+
+/// Language of a POSIX locale name, e.g. "de" for "de_DE.UTF-8"
+///
+/// Returns None for the "C" and "POSIX" locales.
+pub fn language_of(locale: &str) -> Option<String> {
+    let language = locale.split(['_', '.', '@', '-']).next().unwrap_or_default().to_lowercase();
+    match language.as_str() {
+        "" | "c" | "posix" => None,
+        _ => Some(language),
+    }
+}
+
+/// Search locales from the environment, most preferred first
+///
+/// Uses `$LANGUAGE` (a colon-separated list), or else the first of
+/// `$LC_ALL`, `$LC_MESSAGES` and `$LANG` that is set. English is always
+/// included as the last fallback.
+pub fn locales_from_env() -> Vec<String> {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let mut locales: Vec<String> = Vec::new();
+
+    let candidates: Vec<String> = match var("LANGUAGE") {
+        Some(list) => list.split(':').map(|s| s.to_string()).collect(),
+        None => ["LC_ALL", "LC_MESSAGES", "LANG"].iter().filter_map(|name| var(name)).take(1).collect(),
+    };
+    for language in candidates.iter().filter_map(|c| language_of(c)) {
+        if !locales.contains(&language) {
+            locales.push(language);
+        }
+    }
+    if !locales.iter().any(|l| l == "en") {
+        locales.push("en".to_string());
+    }
+    locales
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vendored_locales() {
+        let locales: Vec<&str> = ANNOTATION_DATA.iter().map(|(locale, _)| *locale).collect();
+        for locale in ["de", "en", "es", "fr"] {
+            assert!(locales.contains(&locale), "locale '{}' missing", locale);
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let annotation = get("de", "😂").expect("😂 missing in de");
+        assert_eq!(annotation.name, "Gesicht mit Freudentränen");
+        assert!(annotation.keywords.contains(&"lachen"));
+
+        // Variation selectors are ignored
+        assert_eq!(get("fr", "❤️").map(|a| a.name), Some("cœur rouge"));
+        assert_eq!(get("fr", "❤").map(|a| a.name), Some("cœur rouge"));
+
+        assert!(get("xx", "😂").is_none());
+    }
+
+    #[test]
+    fn test_index() {
+        for (locale, annotations) in ANNOTATION_DATA {
+            for annotation in *annotations {
+                let found = get(locale, annotation.emoji).unwrap_or_else(|| panic!("{} missing in {}", annotation.emoji, locale));
+                assert_eq!(found.emoji, annotation.emoji);
+                assert_eq!(annotation.search_terms[0], annotation.name.to_lowercase());
+                assert_eq!(annotation.search_terms.len(), annotation.keywords.len() + 1);
+            }
+        }
+        // Forms with variation selectors are in the index as well
+        for locale in ["de", "en", "es", "fr"] {
+            assert_eq!(get(locale, "❤️").map(|a| a.emoji), Some("❤"));
+        }
+        assert!(get("en", ":-)").is_none());
+    }
+
+    #[test]
+    fn test_derived_flags() {
+        assert_eq!(get("de", "🇩🇪").map(|a| a.name), Some("Flagge: Deutschland"));
+        assert_eq!(get("en", "🏴󠁧󠁢󠁥󠁮󠁧󠁿").map(|a| a.name), Some("flag: England"));
+        assert!(get("fr", "🇯🇵").unwrap().keywords.contains(&"Japon"));
+    }
+
+    #[test]
+    fn test_language_of() {
+        assert_eq!(language_of("de_DE.UTF-8").as_deref(), Some("de"));
+        assert_eq!(language_of("fr").as_deref(), Some("fr"));
+        assert_eq!(language_of("sr@latin").as_deref(), Some("sr"));
+        assert_eq!(language_of("C.UTF-8"), None);
+        assert_eq!(language_of("POSIX"), None);
+    }
+}
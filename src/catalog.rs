@@ -0,0 +1,350 @@
+//! Cheat sheets of the catalog for `smile export-catalog`
+//!
+//! The catalog is written as the picker shows it: the categories in the
+//! order of the `[layout]`, each emoticon with its code points, its name and
+//! the keywords of the settings. The keywords of an emoticon are its own
+//! `[emoticon_keywords]` followed by the `[keywords]` of its category, so a
+//! row can be read on its own, e.g. in a spreadsheet.
+
+use crate::emoticons;
+use crate::favorites::Favorite;
+use crate::settings::{LayoutCategory, Settings};
+use std::fmt;
+use std::str::FromStr;
+
+/// Category the favorites are exported in
+pub const FAVORITES_CATEGORY: &str = "Favorites";
+
+/// Output format of a cheat sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Html,
+    Markdown,
+    Csv,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Html, Format::Markdown, Format::Csv];
+
+    /// Name of the format on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Html => "html",
+            Format::Markdown => "md",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "markdown" => Ok(Format::Markdown),
+            name => Format::ALL.into_iter().find(|format| format.name() == name).ok_or_else(|| {
+                let names: Vec<&str> = Format::ALL.iter().map(|format| format.name()).collect();
+                format!("unknown format '{}', expected one of {}", s, names.join(", "))
+            }),
+        }
+    }
+}
+
+/// An emoticon of the cheat sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub emoticon: String,
+    /// Code points, e.g. "U+1F389"
+    pub codepoints: String,
+    /// Unicode name of an emoji or name of a favorite; empty for text emoticons
+    pub name: String,
+    pub keywords: Vec<String>,
+}
+
+/// A category of the cheat sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Name shown in the picker
+    pub category: String,
+    pub entries: Vec<Entry>,
+}
+
+/// Keywords of an emoticon from the settings, without duplicates
+fn keywords(settings: &Settings, category: &str, emoticon: &str) -> Vec<String> {
+    let own = settings.emoticon_keywords.get(emoticon).map(|k| k.terms.as_slice()).unwrap_or(&[]);
+    let of_category = settings
+        .keywords
+        .get(&category.to_lowercase())
+        .map(|k| k.terms.as_slice())
+        .unwrap_or(&[]);
+
+    let mut keywords: Vec<String> = Vec::new();
+    for term in own.iter().chain(of_category) {
+        if !keywords.iter().any(|k| k.eq_ignore_ascii_case(term)) {
+            keywords.push(term.clone());
+        }
+    }
+    keywords
+}
+
+/// Sections of the categories as the picker shows them
+pub fn from_categories(settings: &Settings, categories: &[LayoutCategory]) -> Vec<Section> {
+    categories
+        .iter()
+        .map(|category| Section {
+            category: category.label.clone(),
+            entries: category
+                .emoticons
+                .iter()
+                .map(|emoticon| Entry {
+                    emoticon: emoticon.to_string(),
+                    codepoints: emoticons::format_codepoints(emoticon),
+                    name: emoticons::lookup(emoticon).map(|e| e.name.to_string()).unwrap_or_default(),
+                    keywords: keywords(settings, &category.name, emoticon),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// A section of the favorites, with their own keywords first
+pub fn from_favorites(settings: &Settings, favorites: &[Favorite]) -> Section {
+    let entries = favorites
+        .iter()
+        .map(|favorite| {
+            let mut keywords = favorite.keywords.clone();
+            for term in self::keywords(settings, FAVORITES_CATEGORY, &favorite.text) {
+                if !keywords.iter().any(|k| k.eq_ignore_ascii_case(&term)) {
+                    keywords.push(term);
+                }
+            }
+            Entry {
+                emoticon: favorite.text.clone(),
+                codepoints: emoticons::format_codepoints(&favorite.text),
+                name: favorite.name.clone(),
+                keywords,
+            }
+        })
+        .collect();
+    Section {
+        category: FAVORITES_CATEGORY.to_string(),
+        entries,
+    }
+}
+
+/// Write the sections in a format
+pub fn write(sections: &[Section], format: Format) -> String {
+    match format {
+        Format::Html => to_html(sections),
+        Format::Markdown => to_markdown(sections),
+        Format::Csv => to_csv(sections),
+    }
+}
+
+/// A standalone HTML page with a table per category
+fn to_html(sections: &[Section]) -> String {
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Emoticons</title>\n\
+         <style>\ntable { border-collapse: collapse; }\nth, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n\
+         td.emoticon { font-size: 1.5em; }\n</style>\n</head>\n<body>\n",
+    );
+    for section in sections {
+        html.push_str(&format!("<h2>{}</h2>\n<table>\n", escape(&section.category)));
+        html.push_str("<tr><th>Emoticon</th><th>Name</th><th>Code points</th><th>Keywords</th></tr>\n");
+        for entry in &section.entries {
+            html.push_str(&format!(
+                "<tr><td class=\"emoticon\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&entry.emoticon),
+                escape(&entry.name),
+                escape(&entry.codepoints),
+                escape(&entry.keywords.join(", "))
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// A Markdown document with a table per category
+fn to_markdown(sections: &[Section]) -> String {
+    // Text emoticons are full of Markdown syntax, e.g. ¯\_(ツ)_/¯
+    fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if "\\`*_{}[]<>()#+-.!|~".contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    let mut markdown = String::from("# Emoticons\n");
+    for section in sections {
+        markdown.push_str(&format!("\n## {}\n\n", escape(&section.category)));
+        markdown.push_str("| Emoticon | Name | Code points | Keywords |\n|---|---|---|---|\n");
+        for entry in &section.entries {
+            markdown.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                escape(&entry.emoticon),
+                escape(&entry.name),
+                entry.codepoints,
+                escape(&entry.keywords.join(", "))
+            ));
+        }
+    }
+    markdown
+}
+
+/// CSV with a header and a row per emoticon; keywords are separated by "; "
+fn to_csv(sections: &[Section]) -> String {
+    fn field(text: &str) -> String {
+        if text.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
+        }
+    }
+
+    let mut csv = String::from("category,emoticon,codepoints,name,keywords\r\n");
+    for section in sections {
+        for entry in &section.entries {
+            let row = [
+                field(&section.category),
+                field(&entry.emoticon),
+                field(&entry.codepoints),
+                field(&entry.name),
+                field(&entry.keywords.join("; ")),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push_str("\r\n");
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{CategoryKeywords, EmoticonKeywords};
+
+    fn settings() -> Settings {
+        let mut settings = Settings::default();
+        settings.keywords.clear();
+        settings.emoticon_keywords.clear();
+        settings.emoticon_keywords.insert(
+            "🎉".to_string(),
+            EmoticonKeywords::new(vec!["release".to_string(), "Party".to_string()]),
+        );
+        settings.keywords.insert(
+            "classic".to_string(),
+            CategoryKeywords::new(vec!["ascii".to_string()]),
+        );
+        settings.keywords.insert(
+            "activities".to_string(),
+            CategoryKeywords::new(vec!["party".to_string(), "fun".to_string()]),
+        );
+        settings
+    }
+
+    fn sections() -> Vec<Section> {
+        let categories = vec![
+            LayoutCategory {
+                name: "Activities".to_string(),
+                label: "Fun".to_string(),
+                emoticons: vec!["🎉"],
+            },
+            LayoutCategory {
+                name: "Classic".to_string(),
+                label: "Classic".to_string(),
+                emoticons: vec!["¯\\_(ツ)_/¯"],
+            },
+        ];
+        from_categories(&settings(), &categories)
+    }
+
+    #[test]
+    fn test_format_parse() {
+        assert_eq!("md".parse(), Ok(Format::Markdown));
+        assert_eq!("Markdown".parse(), Ok(Format::Markdown));
+        assert_eq!("HTML".parse(), Ok(Format::Html));
+        assert!("pdf".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_from_categories() {
+        let sections = sections();
+        assert_eq!(sections[0].category, "Fun");
+        let party = &sections[0].entries[0];
+        assert_eq!(party.codepoints, "U+1F389");
+        assert_eq!(party.name, "party popper");
+        // Own keywords first, then the category's, without the second "party"
+        assert_eq!(party.keywords, vec!["release", "Party", "fun"]);
+        assert_eq!(sections[1].entries[0].name, "");
+        assert_eq!(sections[1].entries[0].keywords, vec!["ascii"]);
+    }
+
+    #[test]
+    fn test_from_favorites() {
+        let favorite = Favorite {
+            text: "🎉🥳".to_string(),
+            name: "Party".to_string(),
+            keywords: vec!["celebrate".to_string()],
+        };
+        let section = from_favorites(&settings(), &[favorite]);
+        assert_eq!(section.category, FAVORITES_CATEGORY);
+        assert_eq!(section.entries[0].codepoints, "U+1F389 U+1F973");
+        assert_eq!(section.entries[0].name, "Party");
+        assert_eq!(section.entries[0].keywords, vec!["celebrate"]);
+    }
+
+    #[test]
+    fn test_write() {
+        let sections = sections();
+        let csv = write(&sections, Format::Csv);
+        assert_eq!(
+            csv,
+            "category,emoticon,codepoints,name,keywords\r\n\
+             Fun,🎉,U+1F389,party popper,release; Party; fun\r\n\
+             Classic,¯\\_(ツ)_/¯,U+00AF U+005C U+005F U+0028 U+30C4 U+0029 U+005F U+002F U+00AF,,ascii\r\n"
+        );
+
+        let markdown = write(&sections, Format::Markdown);
+        assert!(markdown.contains("\n## Fun\n"));
+        assert!(markdown.contains("| 🎉 | party popper | U+1F389 | release, Party, fun |\n"));
+        assert!(markdown.contains("| ¯\\\\\\_\\(ツ\\)\\_/¯ |"));
+
+        let html = write(&sections, Format::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h2>Fun</h2>"));
+        assert!(html.contains("<td class=\"emoticon\">🎉</td><td>party popper</td>"));
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        let sections = vec![Section {
+            category: "Custom".to_string(),
+            entries: vec![Entry {
+                emoticon: "\",\"".to_string(),
+                codepoints: String::new(),
+                name: String::new(),
+                keywords: vec!["a".to_string(), "b".to_string()],
+            }],
+        }];
+        assert!(write(&sections, Format::Csv).ends_with("Custom,\"\"\",\"\"\",,,a; b\r\n"));
+    }
+}
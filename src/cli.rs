@@ -0,0 +1,498 @@
+//! Command line interface
+//!
+//! `smile` without arguments opens the picker; `smile <command> ...` runs a
+//! command in the terminal instead:
+//!
+//! ```text
+//! smile import --from rofimoji|emote|splatmoji <path>
+//! smile update-data <dir>
+//! smile style <style>|all [text...]
+//! smile emojize|demojize|describe [options] < text
+//! smile render <emoji> [--size <px>] [--padding <px>] [--background <color>] --out <file>
+//! smile export-catalog --format html|md|csv [--favorites | --category <name>] [--out <file>]
+//! ```
+
+use crate::catalog;
+use crate::data_cache::{self, DataCache};
+use crate::emoticons;
+use crate::favorites;
+use crate::filters;
+use crate::import::{self, Source};
+use crate::render::{self, Options};
+use crate::settings::Config;
+use crate::shortcodes::ShortcodeSet;
+use crate::textstyle::Style;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "Usage:
+  smile                                              Open the emoticon picker
+  smile import --from rofimoji|emote|splatmoji <path>
+                                                     Import keywords and recently used emoticons
+  smile update-data <dir>                            Use emoji-test.txt and CLDR annotations from <dir>
+  smile style bold|italic|mono|circled|strike|upside-down|all [text...]
+                                                     Write the text, or stdin, in a text style
+  smile emojize [--shortcodes github|slack|discord] [--no-smileys]
+                                                     Turn :shortcodes: and smileys on stdin into emoji
+  smile demojize [--shortcodes github|slack|discord] [--smileys]
+                                                     Turn emoji on stdin into :shortcodes: or smileys
+  smile describe                                     Write the name after each emoji on stdin
+  smile render <emoji> [--size <px>] [--padding <px>] [--background <color>] --out <file.png|file.svg>
+                                                     Draw an emoji as an image, 256 pixels and transparent by default
+  smile export-catalog --format html|md|csv [--favorites | --category <name>] [--out <file>]
+                                                     Write the categories, emoticons and keywords as a cheat sheet
+  smile help                                         Show this help";
+
+/// Run a command line command
+///
+/// Returns the exit code, or None if the arguments are for the picker.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, args) = args.split_first()?;
+    let code = match command.as_str() {
+        "import" => import_command(args),
+        "update-data" => match args {
+            [dir] => update_data_command(Path::new(dir)),
+            _ => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "style" => match args.split_first() {
+            Some((style, text)) => style_command(style, text),
+            None => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "emojize" | "demojize" | "describe" => filter_command(command, args),
+        "render" => render_command(args),
+        "export-catalog" => export_catalog_command(args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            0
+        }
+        // Options like --gapplication-service are left to GTK
+        _ if command.starts_with('-') => return None,
+        _ => {
+            eprintln!("Unknown command '{}'\n\n{}", command, USAGE);
+            2
+        }
+    };
+    Some(code)
+}
+
+/// `smile import --from <picker> <path>`
+fn import_command(args: &[String]) -> i32 {
+    let mut source = None;
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = if arg == "--from" {
+            args.next().map(String::as_str)
+        } else if let Some(value) = arg.strip_prefix("--from=") {
+            Some(value)
+        } else if path.is_none() && !arg.starts_with('-') {
+            path = Some(PathBuf::from(arg));
+            continue;
+        } else {
+            eprintln!("Unexpected argument '{}'\n\n{}", arg, USAGE);
+            return 2;
+        };
+        match value.map(str::parse::<Source>) {
+            Some(Ok(value)) => source = Some(value),
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+            None => {
+                eprintln!("--from needs the name of a picker\n\n{}", USAGE);
+                return 2;
+            }
+        }
+    }
+    let (Some(source), Some(path)) = (source, path) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let imported = match import::read(source, &path) {
+        Ok(imported) => imported,
+        Err(e) => {
+            eprintln!("Failed to import from {}: {}", source, e);
+            return 1;
+        }
+    };
+    let mut config = match Config::new() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load settings: {}", e);
+            return 1;
+        }
+    };
+
+    println!("Read {} {} file(s) from {}", imported.files.len(), source, path.display());
+    let merge = match config.merge_emoticon_keywords(&imported.keywords) {
+        Ok(merge) => merge,
+        Err(e) => {
+            eprintln!("Failed to save keywords: {}", e);
+            return 1;
+        }
+    };
+    println!(
+        "Keywords: {} added to {} emoticon(s), {} skipped because the search already finds them",
+        merge.added, merge.updated, merge.known
+    );
+    if !merge.conflicts.is_empty() {
+        println!(
+            "Not updated, please add the keywords to settings.toml by hand: {}",
+            merge.conflicts.join(" ")
+        );
+    }
+
+    match config.merge_recent(&imported.recent) {
+        Ok(added) => {
+            println!("Recently used: {} of {} emoticon(s) added", added, imported.recent.len());
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to save recently used emoticons: {}", e);
+            1
+        }
+    }
+}
+
+/// `smile update-data <dir>`
+fn update_data_command(dir: &Path) -> i32 {
+    let config = match Config::new() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load settings: {}", e);
+            return 1;
+        }
+    };
+    // Changes are reported against the data in use, which may be an older cache
+    data_cache::use_cache(config.config_dir());
+
+    let cache = match DataCache::compile(dir) {
+        Ok(cache) => cache,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let file = data_cache::cache_file(config.config_dir());
+    if let Err(e) = cache.save(&file) {
+        eprintln!("Failed to write {}: {}", file.display(), e);
+        return 1;
+    }
+
+    let version = cache.version().map(|v| v.to_string()).unwrap_or_default();
+    println!("Wrote {} entries up to Emoji {} to {}", cache.emoji.len(), version, file.display());
+    if !cache.annotations.is_empty() {
+        let locales: Vec<&str> = cache.annotations.keys().map(String::as_str).collect();
+        println!("Annotations: {}", locales.join(", "));
+    }
+
+    let changes = cache.changes(emoticons::emoji_data());
+    println!("Added {} emoji", changes.added.len());
+    for entry in &changes.added {
+        println!("  + {}  {} (E{})", entry.emoji, entry.name, entry.version);
+    }
+    println!("Removed {} emoji", changes.removed.len());
+    for (emoji, name) in &changes.removed {
+        println!("  - {}  {}", emoji, name);
+    }
+
+    if cache.is_older_than_built_in() {
+        println!("Note: the built-in data is newer, so the cache is ignored until it is updated again");
+    }
+    0
+}
+
+/// `smile style <style>|all [text...]`
+fn style_command(style: &str, text: &[String]) -> i32 {
+    let styles = if style == "all" {
+        Style::ALL.to_vec()
+    } else {
+        match style.parse::<Style>() {
+            Ok(style) => vec![style],
+            Err(e) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+        }
+    };
+
+    let text = if text.is_empty() {
+        let mut input = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut input) {
+            eprintln!("Failed to read stdin: {}", e);
+            return 1;
+        }
+        // Keep the text's own line breaks, but not the one echo adds
+        input.strip_suffix('\n').unwrap_or(&input).to_string()
+    } else {
+        text.join(" ")
+    };
+
+    match styles.as_slice() {
+        [style] => println!("{}", style.apply(&text)),
+        _ => {
+            for style in styles {
+                println!("{:<12} {}", style.name(), style.apply(&text));
+            }
+        }
+    }
+    0
+}
+
+/// `smile emojize|demojize|describe [options]`
+///
+/// Works line by line, so the output of a long-running pipe shows up as it comes.
+fn filter_command(command: &str, args: &[String]) -> i32 {
+    let config = match Config::new() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load settings: {}", e);
+            return 1;
+        }
+    };
+    // The same emoji and annotations as in the picker
+    data_cache::use_cache(config.config_dir());
+
+    let mut set = config.settings().shortcodes;
+    let mut smileys = command == "emojize";
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--shortcodes" if command != "describe" => args.next().map(String::as_str),
+            _ if command != "describe" && arg.starts_with("--shortcodes=") => arg.strip_prefix("--shortcodes="),
+            "--no-smileys" if command == "emojize" => {
+                smileys = false;
+                continue;
+            }
+            "--smileys" if command == "demojize" => {
+                smileys = true;
+                continue;
+            }
+            _ => {
+                eprintln!("Unexpected argument '{}'\n\n{}", arg, USAGE);
+                return 2;
+            }
+        };
+        match value.map(str::parse::<ShortcodeSet>) {
+            Some(Ok(value)) => set = value,
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+            None => {
+                eprintln!("Missing value of --shortcodes\n\n{}", USAGE);
+                return 2;
+            }
+        }
+    }
+
+    let filter = |line: &str| match command {
+        "emojize" => filters::emojize(line, set, smileys),
+        "demojize" => filters::demojize(line, set, smileys),
+        _ => filters::describe(line, config.search_locales()),
+    };
+
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match input.read_line(&mut line) {
+            Ok(0) => return 0,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Failed to read stdin: {}", e);
+                return 1;
+            }
+        }
+        let written = output.write_all(filter(&line).as_bytes()).and_then(|_| output.flush());
+        match written {
+            Ok(()) => {}
+            // The reader is gone, e.g. `smile describe < log | head`
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return 0,
+            Err(e) => {
+                eprintln!("Failed to write stdout: {}", e);
+                return 1;
+            }
+        }
+    }
+}
+
+/// `smile render <emoji> [options] --out <file>`
+fn render_command(args: &[String]) -> i32 {
+    let config = match Config::new() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load settings: {}", e);
+            return 1;
+        }
+    };
+    let mut options = Options {
+        font: config.settings().emoji_font.clone().unwrap_or_else(|| render::DEFAULT_FONT.to_string()),
+        ..Options::default()
+    };
+    let mut text = None;
+    let mut out = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        if !name.starts_with("--") && text.is_none() {
+            text = Some(arg.clone());
+            continue;
+        }
+        let Some(value) = inline.or_else(|| args.next().cloned()) else {
+            eprintln!("Missing value of {}\n\n{}", name, USAGE);
+            return 2;
+        };
+        let parsed = match name {
+            "--size" => value.parse().map(|size| options.size = size).map_err(|_| ()),
+            "--padding" => value.parse().map(|padding| options.padding = padding).map_err(|_| ()),
+            "--background" => gtk::gdk::RGBA::parse(value.as_str())
+                .map(|color| options.background = Some(color))
+                .map_err(|_| ()),
+            "--out" => {
+                out = Some(PathBuf::from(&value));
+                Ok(())
+            }
+            _ => {
+                eprintln!("Unexpected argument '{}'\n\n{}", arg, USAGE);
+                return 2;
+            }
+        };
+        if parsed.is_err() {
+            eprintln!("Invalid value '{}' of {}", value, name);
+            return 2;
+        }
+    }
+
+    let (Some(text), Some(out)) = (text, out) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    if let Err(e) = render::Format::of(&out).and_then(|_| options.validate()) {
+        eprintln!("{}", e);
+        return 2;
+    }
+
+    match render::render_to_file(&text, &options, &out) {
+        Ok(()) => {
+            println!("Wrote {}", out.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// `smile export-catalog --format <format> [--favorites | --category <name>] [--out <file>]`
+fn export_catalog_command(args: &[String]) -> i32 {
+    let mut format = None;
+    let mut favorites = false;
+    let mut category = None;
+    let mut out = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--favorites" {
+            favorites = true;
+            continue;
+        }
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        if !matches!(name, "--format" | "--category" | "--out") {
+            eprintln!("Unexpected argument '{}'\n\n{}", arg, USAGE);
+            return 2;
+        }
+        let Some(value) = inline.or_else(|| args.next().cloned()) else {
+            eprintln!("Missing value of {}\n\n{}", name, USAGE);
+            return 2;
+        };
+        match name {
+            "--format" => match value.parse::<catalog::Format>() {
+                Ok(value) => format = Some(value),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return 2;
+                }
+            },
+            "--category" => category = Some(value),
+            _ => out = Some(PathBuf::from(value)),
+        }
+    }
+    let Some(format) = format else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    if favorites && category.is_some() {
+        eprintln!("--favorites and --category can't be used together\n\n{}", USAGE);
+        return 2;
+    }
+
+    let config = match Config::new() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load settings: {}", e);
+            return 1;
+        }
+    };
+    // The same emoticons as in the picker
+    data_cache::use_cache(config.config_dir());
+    emoticons::use_custom_emoticons(config.config_dir());
+    let settings = config.settings();
+
+    let sections = if favorites {
+        let (favorites, errors) = favorites::load_favorites(&config.config_dir().join("favorites.toml"));
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        vec![catalog::from_favorites(settings, &favorites)]
+    } else {
+        let mut categories = settings.categories(emoticons::get_emoticons());
+        if let Some(name) = &category {
+            categories.retain(|c| c.name.eq_ignore_ascii_case(name) || c.label.eq_ignore_ascii_case(name));
+            if categories.is_empty() {
+                eprintln!("No category '{}'", name);
+                return 1;
+            }
+        }
+        catalog::from_categories(settings, &categories)
+    };
+    let text = catalog::write(&sections, format);
+
+    let written = match &out {
+        Some(path) => std::fs::write(path, text),
+        None => io::stdout().lock().write_all(text.as_bytes()),
+    };
+    match written {
+        Ok(()) => {
+            if let Some(path) = out {
+                println!("Wrote {}", path.display());
+            }
+            0
+        }
+        // The reader is gone, e.g. `smile export-catalog --format csv | head`
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(e) => {
+            match out {
+                Some(path) => eprintln!("Failed to write {}: {}", path.display(), e),
+                None => eprintln!("Failed to write stdout: {}", e),
+            }
+            1
+        }
+    }
+}
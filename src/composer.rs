@@ -0,0 +1,202 @@
+//! ZWJ sequence composer
+//!
+//! Groups the person, family and other ZWJ sequences of the emoji database
+//! into roles with person variants, e.g. the role "technologist" with the
+//! variants person 🧑‍💻, man 👨‍💻 and woman 👩‍💻. Combined with a skin tone
+//! this gives every RGI sequence, and only those.
+
+use crate::emoticons::{self, Qualification, SkinTone};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Zero width joiner used to build emoji sequences
+pub const ZWJ: char = '\u{200D}';
+
+/// Name of the role collecting ZWJ sequences that don't show people (🏳️‍🌈, ❤️‍🔥, ...)
+pub const OTHER_ROLE: &str = "other sequences";
+
+/// Name prefixes describing the people shown by an emoji, longest first
+const PERSON_PREFIXES: &[&str] = &["woman and man", "people", "person", "women", "woman", "men", "man"];
+
+/// A role such as "technologist" with its person variants
+#[derive(Debug)]
+pub struct Role {
+    /// Role name, e.g. "technologist"
+    pub name: String,
+    /// Variants as (label, emoji without skin tone), e.g. ("woman", "👩‍💻")
+    pub variants: Vec<(String, &'static str)>,
+}
+
+static ROLES: OnceLock<Vec<Role>> = OnceLock::new();
+
+/// Split an emoji name into its person variant and its role
+///
+/// "woman technologist" gives ("woman", "technologist"),
+/// "family: man, woman, boy" gives ("man, woman, boy", "family").
+fn split_name(name: &str) -> (String, String) {
+    for prefix in PERSON_PREFIXES {
+        if let Some(rest) = name.strip_prefix(prefix) {
+            if let Some(role) = rest.strip_prefix(": ").or_else(|| rest.strip_prefix(' ')) {
+                return (prefix.to_string(), role.to_string());
+            }
+        }
+    }
+    if let Some((role, variant)) = name.split_once(": ") {
+        return (variant.to_string(), role.to_string());
+    }
+    ("person".to_string(), name.to_string())
+}
+
+/// Get all roles offered by the composer, in CLDR order
+pub fn roles() -> &'static [Role] {
+    ROLES.get_or_init(|| {
+        let mut roles: Vec<Role> = Vec::new();
+        let mut role_index: HashMap<String, usize> = HashMap::new();
+        let mut other = Role {
+            name: OTHER_ROLE.to_string(),
+            variants: Vec::new(),
+        };
+
+        // Skin tones are applied on top, so only the base sequences are collected
+        let candidates = emoticons::emoji_data()
+            .iter()
+            .filter(|e| e.status == Qualification::FullyQualified)
+            .filter(|e| emoticons::skin_tone_of(e.emoji) == Some(SkinTone::None));
+
+        for entry in candidates {
+            let is_person = entry.group == "People & Body"
+                && (entry.subgroup.starts_with("person") || entry.subgroup == "family");
+
+            if is_person {
+                let (variant, role) = split_name(entry.name);
+                let index = *role_index.entry(role.clone()).or_insert_with(|| {
+                    roles.push(Role {
+                        name: role,
+                        variants: Vec::new(),
+                    });
+                    roles.len() - 1
+                });
+                roles[index].variants.push((variant, entry.emoji));
+            } else if entry.emoji.contains(ZWJ) {
+                other.variants.push((entry.name.to_string(), entry.emoji));
+            }
+        }
+
+        // Roles without any ZWJ sequence (baby, person, ...) are plain emoji
+        roles.retain(|role| role.variants.iter().any(|(_, emoji)| emoji.contains(ZWJ)));
+        roles.push(other);
+        roles
+    })
+}
+
+/// Compose the emoji for a role variant and a skin tone
+///
+/// The skin tone is ignored for sequences that don't take one. Returns
+/// None if the sequence doesn't exist with the requested skin tone.
+pub fn compose(variant: &str, tone: SkinTone) -> Option<&'static str> {
+    if !emoticons::supports_skin_tone(variant) {
+        return emoticons::lookup(variant).map(|e| e.emoji);
+    }
+    emoticons::with_skin_tone(variant, tone)
+}
+
+/// Describe the parts of a ZWJ sequence, e.g. "👩 woman + 💻 laptop"
+pub fn describe_parts(emoji: &str) -> String {
+    emoji
+        .split(ZWJ)
+        .map(|part| {
+            // Parts usually drop their variation selector inside sequences
+            let with_selector = format!("{}\u{FE0F}", part.trim_end_matches('\u{FE0F}'));
+            match emoticons::lookup(part).or_else(|| emoticons::lookup(&with_selector)) {
+                Some(entry) => format!("{} {}", part, entry.name),
+                None => part.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(name: &str) -> &'static Role {
+        roles()
+            .iter()
+            .find(|r| r.name == name)
+            .unwrap_or_else(|| panic!("role '{}' missing", name))
+    }
+
+    fn variant(role: &Role, label: &str) -> &'static str {
+        role.variants
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, emoji)| *emoji)
+            .unwrap_or_else(|| panic!("variant '{}' of '{}' missing", label, role.name))
+    }
+
+    #[test]
+    fn test_split_name() {
+        assert_eq!(split_name("woman technologist"), ("woman".to_string(), "technologist".to_string()));
+        assert_eq!(split_name("person: red hair"), ("person".to_string(), "red hair".to_string()));
+        assert_eq!(
+            split_name("woman and man holding hands"),
+            ("woman and man".to_string(), "holding hands".to_string())
+        );
+        assert_eq!(split_name("family: man, woman, boy"), ("man, woman, boy".to_string(), "family".to_string()));
+        assert_eq!(split_name("technologist"), ("person".to_string(), "technologist".to_string()));
+    }
+
+    #[test]
+    fn test_professions() {
+        let technologist = role("technologist");
+        assert_eq!(variant(technologist, "woman"), "👩‍💻");
+        assert_eq!(variant(technologist, "man"), "👨‍💻");
+        assert_eq!(compose(variant(technologist, "woman"), SkinTone::Medium), Some("👩🏽‍💻"));
+        assert_eq!(compose(variant(technologist, "woman"), SkinTone::None), Some("👩‍💻"));
+    }
+
+    #[test]
+    fn test_gendered_variants() {
+        let detective = role("detective");
+        assert_eq!(variant(detective, "person"), "🕵️");
+        assert_eq!(variant(detective, "woman"), "🕵️‍♀️");
+    }
+
+    #[test]
+    fn test_families() {
+        let holding_hands = role("holding hands");
+        assert_eq!(variant(holding_hands, "people"), "🧑‍🤝‍🧑");
+        assert_eq!(compose(variant(holding_hands, "people"), SkinTone::Dark), Some("🧑🏿‍🤝‍🧑🏿"));
+        assert!(role("family").variants.len() > 10);
+    }
+
+    #[test]
+    fn test_other_sequences() {
+        let other = role(OTHER_ROLE);
+        let rainbow_flag = variant(other, "rainbow flag");
+        assert_eq!(rainbow_flag, "🏳️‍🌈");
+        // Skin tones don't apply to flags
+        assert_eq!(compose(rainbow_flag, SkinTone::Dark), Some("🏳️‍🌈"));
+    }
+
+    #[test]
+    fn test_only_rgi_sequences() {
+        for role in roles() {
+            for (_, emoji) in &role.variants {
+                for tone in SkinTone::ALL {
+                    if let Some(result) = compose(emoji, tone) {
+                        let entry = emoticons::lookup(result).expect("composed emoji not in database");
+                        assert_eq!(entry.status, Qualification::FullyQualified);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_describe_parts() {
+        assert_eq!(describe_parts("👩‍💻"), "👩 woman + 💻 laptop");
+        assert_eq!(describe_parts("🏳️‍🌈"), "🏳️ white flag + 🌈 rainbow");
+    }
+}
@@ -0,0 +1,310 @@
+//! User-defined emoticons from ~/.smile/custom.toml
+//!
+//! ```toml
+//! [[emoticon]]
+//! text = "(ノ◕ヮ◕)ノ*:･ﾟ✧"
+//! category = "Kaomoji"           # optional, defaults to "Custom"
+//! keywords = ["sparkle", "magic"] # optional
+//! ```
+//!
+//! Every entry is validated on load. Invalid entries are skipped and
+//! reported with file and line, the valid ones are still loaded. The
+//! kaomoji maker appends its entries to the file, keeping comments and the
+//! existing entries as they are.
+
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Category used for entries without a category
+pub const DEFAULT_CUSTOM_CATEGORY: &str = "Custom";
+
+/// A user-defined emoticon
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomEmoticon {
+    /// Text that is typed, e.g. "(ノ◕ヮ◕)ノ*:･ﾟ✧"
+    pub text: String,
+    /// Category the emoticon is shown in
+    pub category: String,
+    /// Search keywords
+    pub keywords: Vec<String>,
+    /// The keywords in lowercase, for the search
+    search_terms: Vec<String>,
+}
+
+impl CustomEmoticon {
+    pub fn new(text: String, category: String, keywords: Vec<String>) -> Self {
+        let search_terms = keywords.iter().map(|k| k.to_lowercase()).collect();
+        CustomEmoticon {
+            text,
+            category,
+            keywords,
+            search_terms,
+        }
+    }
+
+    /// The keywords in lowercase
+    pub fn search_terms(&self) -> &[String] {
+        &self.search_terms
+    }
+
+    /// The `[[emoticon]]` entry of this emoticon
+    fn to_toml(&self) -> String {
+        let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+        let mut entry = format!("[[emoticon]]\ntext = {}\n", quote(&self.text));
+        if self.category != DEFAULT_CUSTOM_CATEGORY {
+            entry.push_str(&format!("category = {}\n", quote(&self.category)));
+        }
+        if !self.keywords.is_empty() {
+            let keywords: Vec<String> = self.keywords.iter().map(|k| quote(k)).collect();
+            entry.push_str(&format!("keywords = [{}]\n", keywords.join(", ")));
+        }
+        entry
+    }
+}
+
+/// A problem found while loading custom.toml or a pack file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomError {
+    pub file: PathBuf,
+    /// Line number, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CustomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file.display(), self.line, self.message)
+    }
+}
+
+/// Layout of custom.toml; entries are kept as raw values so that a single
+/// invalid entry doesn't reject the whole file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomFile {
+    #[serde(default)]
+    emoticon: Vec<toml::Spanned<toml::Value>>,
+}
+
+/// A single [[emoticon]] entry as written by the user
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEntry {
+    text: String,
+    category: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
+/// Load custom emoticons from a file
+///
+/// A missing file is not an error, it just means there are no custom emoticons.
+pub fn load_custom(path: &Path) -> (Vec<CustomEmoticon>, Vec<CustomError>) {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_custom(path, &content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (Vec::new(), Vec::new()),
+        Err(e) => (
+            Vec::new(),
+            vec![CustomError {
+                file: path.to_path_buf(),
+                line: 1,
+                message: format!("Failed to read file: {}", e),
+            }],
+        ),
+    }
+}
+
+/// Parse and validate the content of a custom emoticons file
+pub fn parse_custom(path: &Path, content: &str) -> (Vec<CustomEmoticon>, Vec<CustomError>) {
+    let line_of = |offset: usize| content[..offset.min(content.len())].matches('\n').count() + 1;
+    let error = |line: usize, message: String| CustomError {
+        file: path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let file: CustomFile = match toml::from_str(content) {
+        Ok(file) => file,
+        Err(e) => {
+            let line = e.span().map(|span| line_of(span.start)).unwrap_or(1);
+            return (Vec::new(), vec![error(line, e.message().to_string())]);
+        }
+    };
+
+    let mut emoticons: Vec<CustomEmoticon> = Vec::new();
+    let mut errors = Vec::new();
+
+    for spanned in file.emoticon {
+        let line = line_of(spanned.span().start);
+        let entry = match spanned.into_inner().try_into::<RawEntry>() {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.push(error(line, format!("Invalid emoticon: {}", e.message())));
+                continue;
+            }
+        };
+
+        let emoticon = CustomEmoticon::new(
+            entry.text,
+            entry.category.unwrap_or_else(|| DEFAULT_CUSTOM_CATEGORY.to_string()),
+            entry.keywords,
+        );
+        match validate(&emoticon, &emoticons) {
+            Some(message) => errors.push(error(line, message)),
+            None => emoticons.push(emoticon),
+        }
+    }
+
+    (emoticons, errors)
+}
+
+/// Check a custom emoticon against the ones loaded before it from the same file
+///
+/// Returns a description of the problem, if any.
+pub fn validate(emoticon: &CustomEmoticon, previous: &[CustomEmoticon]) -> Option<String> {
+    if emoticon.text.trim().is_empty() {
+        Some("Emoticon text is empty".to_string())
+    } else if emoticon.text.chars().any(char::is_control) {
+        Some(format!("Emoticon text '{}' contains control characters", emoticon.text.escape_debug()))
+    } else if emoticon.category.trim().is_empty() {
+        Some(format!("Category of '{}' is empty", emoticon.text))
+    } else if emoticon.keywords.iter().any(|k| k.trim().is_empty()) {
+        Some(format!("Keywords of '{}' contain an empty keyword", emoticon.text))
+    } else if previous
+        .iter()
+        .any(|e| e.text == emoticon.text && e.category == emoticon.category)
+    {
+        Some(format!("'{}' is defined twice in category '{}'", emoticon.text, emoticon.category))
+    } else {
+        None
+    }
+}
+
+/// Append a custom emoticon to the file, creating it if needed
+pub fn append_custom(path: &Path, emoticon: &CustomEmoticon) -> io::Result<()> {
+    append_entry(path, &emoticon.to_toml())
+}
+
+/// Append a TOML entry to a file, separated from the content before it by
+/// an empty line
+pub fn append_entry(path: &Path, entry: &str) -> io::Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let separator = match existing.as_str() {
+        "" => "",
+        s if s.ends_with("\n\n") => "",
+        s if s.ends_with('\n') => "\n",
+        _ => "\n\n",
+    };
+
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    write!(file, "{}{}", separator, entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> (Vec<CustomEmoticon>, Vec<CustomError>) {
+        parse_custom(Path::new("custom.toml"), content)
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let (emoticons, errors) = parse(
+            r#"
+[[emoticon]]
+text = "(ノ◕ヮ◕)ノ*:･ﾟ✧"
+category = "Kaomoji"
+keywords = ["sparkle", "Magic"]
+
+[[emoticon]]
+text = "¯\\_(ツ)_/¯"
+"#,
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(emoticons.len(), 2);
+        assert_eq!(emoticons[0].category, "Kaomoji");
+        assert_eq!(emoticons[0].keywords, vec!["sparkle", "Magic"]);
+        assert_eq!(emoticons[0].search_terms(), ["sparkle", "magic"]);
+        assert_eq!(emoticons[1].text, r"¯\_(ツ)_/¯");
+        assert_eq!(emoticons[1].category, DEFAULT_CUSTOM_CATEGORY);
+        assert!(emoticons[1].keywords.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_entries_are_reported() {
+        let (emoticons, errors) = parse(
+            r#"
+[[emoticon]]
+text = ":-)"
+
+[[emoticon]]
+text = "  "
+
+[[emoticon]]
+text = "ok"
+keyword = ["typo"]
+
+[[emoticon]]
+text = ":-)"
+
+[[emoticon]]
+text = "<3"
+keywords = ["love", ""]
+"#,
+        );
+        assert_eq!(emoticons.len(), 1);
+
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![5, 8, 12, 15]);
+        assert!(errors[1].message.contains("keyword"));
+        assert!(errors[2].message.contains("defined twice"));
+        assert_eq!(errors[0].to_string(), "custom.toml:5: Emoticon text is empty");
+    }
+
+    #[test]
+    fn test_syntax_error_has_line() {
+        let (emoticons, errors) = parse("[[emoticon]]\ntext = \":-)\"\n\n[[emoticon]\ntext = \"x\"\n");
+        assert!(emoticons.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 4);
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let (emoticons, errors) = load_custom(Path::new("/nonexistent/smile/custom.toml"));
+        assert!(emoticons.is_empty());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_append_custom() {
+        let dir = std::env::temp_dir().join(format!("smile_custom_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("custom.toml");
+        fs::write(&path, "# Mine\n[[emoticon]]\ntext = \":-)\"\n").unwrap();
+
+        let angry = CustomEmoticon::new("ヽ(｀Д´)ﾉ".to_string(), "Kaomoji: Made".to_string(), vec!["angry".to_string()]);
+        append_custom(&path, &angry).unwrap();
+        let plain = CustomEmoticon::new("\\o/".to_string(), DEFAULT_CUSTOM_CATEGORY.to_string(), Vec::new());
+        append_custom(&path, &plain).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# Mine\n"));
+        assert!(!content.contains("category = \"Custom\""));
+        let (emoticons, errors) = load_custom(&path);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(emoticons.len(), 3);
+        assert_eq!(emoticons[1], angry);
+        assert_eq!(emoticons[2], plain);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
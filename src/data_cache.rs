@@ -0,0 +1,305 @@
+//! Emoji data cache built by `smile update-data`
+//!
+//! `smile update-data <dir>` compiles a newer `emoji-test.txt` and CLDR
+//! annotation files into `~/.smile/data/emoji-data.json`. At startup the
+//! cache replaces the built-in tables, unless it is older than them, e.g.
+//! after updating smile itself. Delete the file to go back to the built-in
+//! data.
+
+use crate::annotations::{self, Annotation};
+use crate::data_files;
+use crate::emoticons::{self, EmojiEntry, EmojiVersion, Qualification};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Location of the cache in the config directory
+const CACHE_FILE: &str = "data/emoji-data.json";
+
+/// An entry of emoji-test.txt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedEmoji {
+    pub emoji: String,
+    pub group: String,
+    pub subgroup: String,
+    pub name: String,
+    pub status: Qualification,
+    pub version: EmojiVersion,
+}
+
+/// An emoji of a CLDR annotations file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedAnnotation {
+    pub emoji: String,
+    pub name: String,
+    pub keywords: Vec<String>,
+}
+
+/// Emoji table and annotations compiled from local files
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataCache {
+    /// All entries of emoji-test.txt in CLDR order
+    pub emoji: Vec<CachedEmoji>,
+    /// Annotations per locale, looked up before the built-in annotations
+    pub annotations: BTreeMap<String, Vec<CachedAnnotation>>,
+}
+
+/// Emoji added and removed by a new cache, compared to the data in use
+#[derive(Debug, Default, PartialEq)]
+pub struct Changes {
+    /// Fully-qualified emoji that are new
+    pub added: Vec<CachedEmoji>,
+    /// Fully-qualified emoji that are gone, with their names
+    pub removed: Vec<(&'static str, &'static str)>,
+}
+
+/// Path of the cache file
+pub fn cache_file(config_dir: &Path) -> PathBuf {
+    config_dir.join(CACHE_FILE)
+}
+
+/// Use the data cache of the config directory, if there is one
+///
+/// Problems are reported on stderr; the built-in tables are used then.
+/// Must be called before the emoji tables are first used.
+pub fn use_cache(config_dir: &Path) {
+    let file = cache_file(config_dir);
+    let cache = match DataCache::load(&file) {
+        Ok(Some(cache)) => cache,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    if cache.is_older_than_built_in() {
+        eprintln!(
+            "Ignoring {}: its Emoji {} is older than the built-in Emoji {}",
+            file.display(),
+            cache.version().map(|v| v.to_string()).unwrap_or_default(),
+            built_in_version()
+        );
+        return;
+    }
+    cache.install();
+}
+
+/// Newest Emoji version of the built-in table
+fn built_in_version() -> EmojiVersion {
+    emoticons::EMOJI_DATA
+        .iter()
+        .map(|e| e.version)
+        .max()
+        .unwrap_or(EmojiVersion { major: 0, minor: 0 })
+}
+
+impl DataCache {
+    /// Compile `emoji-test.txt` and the CLDR annotation files of a directory
+    ///
+    /// The annotation files (`<locale>.xml`) may be in the directory itself
+    /// or in `annotations/` and `annotationsDerived/` below it, as in the
+    /// `common/` directory of CLDR.
+    pub fn compile(dir: &Path) -> Result<Self, String> {
+        let emoji_test = dir.join("emoji-test.txt");
+        let content = fs::read_to_string(&emoji_test)
+            .map_err(|e| format!("Failed to read {}: {}", emoji_test.display(), e))?;
+        let emoji: Vec<CachedEmoji> = data_files::parse_emoji_test("emoji-test.txt", &content)?
+            .into_iter()
+            .map(|entry| CachedEmoji {
+                emoji: entry.codepoints.iter().filter_map(|&cp| char::from_u32(cp)).collect(),
+                group: entry.group,
+                subgroup: entry.subgroup,
+                name: entry.name,
+                status: match entry.status {
+                    "Component" => Qualification::Component,
+                    "FullyQualified" => Qualification::FullyQualified,
+                    "MinimallyQualified" => Qualification::MinimallyQualified,
+                    _ => Qualification::Unqualified,
+                },
+                version: EmojiVersion {
+                    major: entry.major,
+                    minor: entry.minor,
+                },
+            })
+            .collect();
+        if emoji.is_empty() {
+            return Err(format!("{}: no emoji found", emoji_test.display()));
+        }
+
+        let dirs = ["", "annotations", "annotationsDerived", "annotations-derived"].map(|d| dir.join(d));
+        let annotations = data_files::read_annotations(&dirs)?
+            .into_iter()
+            .map(|(locale, entries)| {
+                let entries = entries
+                    .into_iter()
+                    .map(|a| CachedAnnotation {
+                        emoji: a.emoji,
+                        name: a.name,
+                        keywords: a.keywords,
+                    })
+                    .collect();
+                (locale, entries)
+            })
+            .collect();
+
+        Ok(DataCache { emoji, annotations })
+    }
+
+    /// Load the cache; a missing file is not an error
+    pub fn load(file: &Path) -> Result<Option<Self>, String> {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read {}: {}", file.display(), e)),
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse {}: {}", file.display(), e))
+    }
+
+    /// Save the cache, creating its directory
+    pub fn save(&self, file: &Path) -> std::io::Result<()> {
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(file, serde_json::to_string(self)?)
+    }
+
+    /// Newest Emoji version in the cache
+    pub fn version(&self) -> Option<EmojiVersion> {
+        self.emoji.iter().map(|e| e.version).max()
+    }
+
+    /// Whether the built-in table has newer emoji than the cache
+    pub fn is_older_than_built_in(&self) -> bool {
+        self.version().is_none_or(|v| v < built_in_version())
+    }
+
+    /// Fully-qualified emoji added and removed compared to another table
+    pub fn changes(&self, current: &'static [EmojiEntry]) -> Changes {
+        let fully_qualified = |status: Qualification| status == Qualification::FullyQualified;
+        let old: HashSet<&str> = current.iter().filter(|e| fully_qualified(e.status)).map(|e| e.emoji).collect();
+        let new: HashSet<&str> = self.emoji.iter().filter(|e| fully_qualified(e.status)).map(|e| e.emoji.as_str()).collect();
+        Changes {
+            added: self
+                .emoji
+                .iter()
+                .filter(|e| fully_qualified(e.status) && !old.contains(e.emoji.as_str()))
+                .cloned()
+                .collect(),
+            removed: current
+                .iter()
+                .filter(|e| fully_qualified(e.status) && !new.contains(e.emoji))
+                .map(|e| (e.emoji, e.name))
+                .collect(),
+        }
+    }
+
+    /// Make the cache the emoji table and annotations of this process
+    ///
+    /// The strings are leaked, they live as long as the built-in tables.
+    fn install(self) {
+        fn leak(s: String) -> &'static str {
+            Box::leak(s.into_boxed_str())
+        }
+        // Groups and subgroups are shared by many entries
+        let mut names: HashMap<String, &'static str> = HashMap::new();
+        let mut intern = |s: String| *names.entry(s.clone()).or_insert_with(|| leak(s));
+
+        let entries: Vec<EmojiEntry> = self
+            .emoji
+            .into_iter()
+            .map(|e| EmojiEntry {
+                codepoints: Vec::leak(e.emoji.chars().map(|c| c as u32).collect()),
+                emoji: leak(e.emoji),
+                group: intern(e.group),
+                subgroup: intern(e.subgroup),
+                search_name: leak(e.name.to_lowercase()),
+                name: leak(e.name),
+                status: e.status,
+                version: e.version,
+            })
+            .collect();
+        emoticons::set_emoji_data(Vec::leak(entries));
+
+        let locales = self
+            .annotations
+            .into_iter()
+            .map(|(locale, entries)| {
+                let entries: Vec<Annotation> = entries
+                    .into_iter()
+                    .map(|a| Annotation {
+                        search_terms: Vec::leak(
+                            std::iter::once(&a.name).chain(&a.keywords).map(|t| leak(t.to_lowercase())).collect(),
+                        ),
+                        emoji: leak(a.emoji),
+                        name: leak(a.name),
+                        keywords: Vec::leak(a.keywords.into_iter().map(leak).collect()),
+                    })
+                    .collect();
+                (leak(locale), &*Vec::leak(entries))
+            })
+            .collect();
+        annotations::set_annotation_data(locales);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMOJI_TEST: &str = "# Version: 99.0\n# group: Smileys & Emotion\n# subgroup: face-smiling\n\
+        1F600 ; fully-qualified # 😀 E1.0 grinning face\n\
+        263A FE0F ; fully-qualified # ☺️ E0.6 smiling face\n\
+        263A ; unqualified # ☺ E0.6 smiling face\n\
+        # subgroup: face-new\n\
+        1FAFA ; fully-qualified # \u{1FAFA} E99.0 test emoji\n";
+
+    fn write_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("annotations")).unwrap();
+        fs::write(dir.join("emoji-test.txt"), EMOJI_TEST).unwrap();
+        fs::write(
+            dir.join("annotations").join("de.xml"),
+            "<annotation cp=\"\u{1FAFA}\">Test | Probe</annotation>\n<annotation cp=\"\u{1FAFA}\" type=\"tts\">Testemoji</annotation>\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_compile() {
+        let dir = write_dir("smile_data_cache_test");
+        let cache = DataCache::compile(&dir).unwrap();
+        assert_eq!(cache.emoji.len(), 4);
+        assert_eq!(cache.emoji[1].emoji, "☺️");
+        assert_eq!(cache.emoji[2].status, Qualification::Unqualified);
+        assert_eq!(cache.emoji[3].subgroup, "face-new");
+        assert_eq!(cache.version(), Some(EmojiVersion { major: 99, minor: 0 }));
+        assert!(!cache.is_older_than_built_in());
+        assert_eq!(cache.annotations["de"][0].keywords, vec!["Test", "Probe"]);
+
+        // The cache survives a round trip through its file
+        let file = cache_file(&dir.join("config"));
+        cache.save(&file).unwrap();
+        assert_eq!(DataCache::load(&file).unwrap(), Some(cache));
+        assert_eq!(DataCache::load(&dir.join("missing.json")).unwrap(), None);
+
+        assert!(DataCache::compile(&dir.join("annotations")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_changes() {
+        let dir = write_dir("smile_data_changes_test");
+        let cache = DataCache::compile(&dir).unwrap();
+        let changes = cache.changes(emoticons::EMOJI_DATA);
+        // Only the test emoji is new, nearly everything else is gone
+        assert_eq!(changes.added.iter().map(|e| e.emoji.as_str()).collect::<Vec<_>>(), ["\u{1FAFA}"]);
+        assert!(changes.removed.contains(&("🚀", "rocket")));
+        assert!(!changes.removed.iter().any(|(emoji, _)| *emoji == "😀" || *emoji == "☺️"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+//! Parsers for Unicode's emoji-test.txt and CLDR's annotation files
+//!
+//! Shared by `build.rs`, which compiles the vendored files in `data/` into
+//! the built-in tables, and by `smile update-data`, which compiles newer
+//! files into the data cache. Errors name the file and line.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+/// One parsed line of emoji-test.txt
+pub struct TestEntry {
+    pub codepoints: Vec<u32>,
+    /// Name of the `Qualification` variant, e.g. "FullyQualified"
+    pub status: &'static str,
+    pub group: String,
+    pub subgroup: String,
+    pub name: String,
+    pub major: u8,
+    pub minor: u8,
+}
+
+/// One emoji of a CLDR annotations file
+#[derive(Default)]
+pub struct AnnotationEntry {
+    pub emoji: String,
+    pub name: String,
+    pub keywords: Vec<String>,
+}
+
+/// Parse the emoji-test.txt format:
+///
+/// ```text
+/// # group: Smileys & Emotion
+/// # subgroup: face-smiling
+/// 1F600 ; fully-qualified # 😀 E1.0 grinning face
+/// ```
+pub fn parse_emoji_test(source: &str, content: &str) -> Result<Vec<TestEntry>, String> {
+    let mut entries = Vec::new();
+    let mut group = String::new();
+    let mut subgroup = String::new();
+
+    for (line_no, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("# group:") {
+            group = name.trim().to_string();
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("# subgroup:") {
+            subgroup = name.trim().to_string();
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fail = |what: &str| format!("{}:{}: {}", source, line_no + 1, what);
+
+        let (data, comment) = line.split_once('#').ok_or_else(|| fail("missing comment"))?;
+        let (codepoints, status) = data.split_once(';').ok_or_else(|| fail("missing status"))?;
+
+        let codepoints = codepoints
+            .split_whitespace()
+            .map(|cp| u32::from_str_radix(cp, 16).map_err(|_| fail("invalid code point")))
+            .collect::<Result<Vec<u32>, String>>()?;
+
+        let status = match status.trim() {
+            "component" => "Component",
+            "fully-qualified" => "FullyQualified",
+            "minimally-qualified" => "MinimallyQualified",
+            "unqualified" => "Unqualified",
+            _ => return Err(fail("unknown status")),
+        };
+
+        // Comment is "<glyph> E<major>.<minor> <name>"
+        let mut parts = comment.trim().splitn(3, ' ');
+        let _glyph = parts.next();
+        let version = parts
+            .next()
+            .and_then(|v| v.strip_prefix('E'))
+            .ok_or_else(|| fail("missing version"))?;
+        let name = parts.next().ok_or_else(|| fail("missing name"))?.trim().to_string();
+        let (major, minor) = version.split_once('.').ok_or_else(|| fail("invalid version"))?;
+
+        entries.push(TestEntry {
+            codepoints,
+            status,
+            group: group.clone(),
+            subgroup: subgroup.clone(),
+            name,
+            major: major.parse().map_err(|_| fail("invalid version"))?,
+            minor: minor.parse().map_err(|_| fail("invalid version"))?,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Read the `<locale>.xml` files of several annotation directories
+///
+/// Files of the same locale are merged, so the derived annotations (flags)
+/// end up in the same table as the regular ones. Directories that don't
+/// exist are skipped.
+pub fn read_annotations(dirs: &[PathBuf]) -> Result<BTreeMap<String, Vec<AnnotationEntry>>, String> {
+    let mut locales: BTreeMap<String, Vec<AnnotationEntry>> = BTreeMap::new();
+    for dir in dirs.iter().filter(|d| d.is_dir()) {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "xml"))
+            .collect();
+        files.sort();
+
+        for file in files {
+            let locale = file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let content =
+                fs::read_to_string(&file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+            let source = file.strip_prefix(dir.parent().unwrap_or(dir)).unwrap_or(&file).display().to_string();
+            locales.entry(locale).or_default().extend(parse_annotations(&source, &content)?);
+        }
+    }
+    Ok(locales)
+}
+
+/// Parse the annotations of a CLDR `common/annotations/<locale>.xml` file:
+///
+/// ```text
+/// <annotation cp="😀">face | grin | grinning face</annotation>
+/// <annotation cp="😀" type="tts">grinning face</annotation>
+/// ```
+///
+/// The `common/annotationsDerived` files use the same format.
+pub fn parse_annotations(source: &str, content: &str) -> Result<Vec<AnnotationEntry>, String> {
+    let mut entries: Vec<AnnotationEntry> = Vec::new();
+    // Each emoji has a line for its keywords and one for its name
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (line_no, line) in content.lines().enumerate() {
+        let Some(rest) = line.trim().strip_prefix("<annotation cp=\"") else {
+            continue;
+        };
+        let fail = |what: &str| format!("{}:{}: {}", source, line_no + 1, what);
+
+        let (emoji, rest) = rest.split_once('"').ok_or_else(|| fail("unterminated cp attribute"))?;
+        let (attributes, rest) = rest.split_once('>').ok_or_else(|| fail("unterminated tag"))?;
+        let text = rest
+            .strip_suffix("</annotation>")
+            .ok_or_else(|| fail("missing </annotation>"))?;
+
+        let emoji = unescape_xml(emoji);
+        let text = unescape_xml(text);
+        let index = *positions.entry(emoji).or_insert_with_key(|emoji| {
+            entries.push(AnnotationEntry {
+                emoji: emoji.clone(),
+                ..Default::default()
+            });
+            entries.len() - 1
+        });
+        if attributes.contains("type=\"tts\"") {
+            entries[index].name = text;
+        } else {
+            entries[index].keywords = text.split('|').map(|k| k.trim().to_string()).collect();
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Resolve the predefined XML entities
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
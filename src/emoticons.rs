@@ -1,56 +1,480 @@
-//! Emoticons database - comprehensive list organized by categories
+//! Emoticons database - the full Unicode emoji set plus classic text emoticons
+//!
+//! The emoji tables are generated at build time from the vendored copy of
+//! Unicode's `data/emoji-test.txt` (see `build.rs`), with a perfect hash
+//! index, so looking up an emoji costs the same for any size of the table.
+use crate::custom::{self, CustomEmoticon};
+use crate::packs;
+use crate::providers;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::OnceLock;
-pub static EMOTICONS: OnceLock<HashMap<&'static str, Vec<String>>> = OnceLock::new();
-pub fn get_emoticons() -> &'static HashMap<&'static str, Vec<String>> {
+
+/// Qualification status of an emoji as listed in emoji-test.txt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Qualification {
+    Component,
+    FullyQualified,
+    MinimallyQualified,
+    Unqualified,
+}
+
+/// Emoji version in which an emoji was introduced (e.g. E13.0)
+///
+/// Written as "13.0" or "13" in the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct EmojiVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl fmt::Display for EmojiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for EmojiVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (major, minor) = s.split_once('.').unwrap_or((s, "0"));
+        match (major.parse(), minor.parse()) {
+            (Ok(major), Ok(minor)) => Ok(EmojiVersion { major, minor }),
+            _ => Err(format!("invalid emoji version '{}', expected e.g. \"13.0\"", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for EmojiVersion {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<EmojiVersion> for String {
+    fn from(version: EmojiVersion) -> Self {
+        version.to_string()
+    }
+}
+
+/// A single entry of emoji-test.txt
+#[derive(Debug)]
+pub struct EmojiEntry {
+    /// The emoji itself
+    pub emoji: &'static str,
+    /// Code point sequence of the emoji
+    #[allow(dead_code)]
+    pub codepoints: &'static [u32],
+    /// Group, e.g. "Smileys & Emotion"
+    pub group: &'static str,
+    /// Subgroup, e.g. "face-smiling"
+    #[allow(dead_code)]
+    pub subgroup: &'static str,
+    /// CLDR short name, e.g. "grinning face"
+    pub name: &'static str,
+    /// The name in lowercase, for the search
+    pub search_name: &'static str,
+    /// Qualification status
+    pub status: Qualification,
+    /// Emoji version the entry was introduced in
+    pub version: EmojiVersion,
+}
+
+include!(concat!(env!("OUT_DIR"), "/emoji_data.rs"));
+
+/// Fitzpatrick skin tone of an emoji
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkinTone {
+    /// No modifier, the default yellow emoji
+    #[default]
+    None,
+    Light,
+    MediumLight,
+    Medium,
+    MediumDark,
+    Dark,
+}
+
+impl SkinTone {
+    /// All tones in the order they are offered, from no modifier to dark
+    pub const ALL: [SkinTone; 6] = [
+        SkinTone::None,
+        SkinTone::Light,
+        SkinTone::MediumLight,
+        SkinTone::Medium,
+        SkinTone::MediumDark,
+        SkinTone::Dark,
+    ];
+
+    /// Human readable name of the skin tone
+    pub fn label(self) -> &'static str {
+        match self {
+            SkinTone::None => "No skin tone",
+            SkinTone::Light => "Light",
+            SkinTone::MediumLight => "Medium-light",
+            SkinTone::Medium => "Medium",
+            SkinTone::MediumDark => "Medium-dark",
+            SkinTone::Dark => "Dark",
+        }
+    }
+
+    /// Skin tone of an emoji modifier character
+    fn from_modifier(c: char) -> Option<SkinTone> {
+        match c {
+            '\u{1F3FB}' => Some(SkinTone::Light),
+            '\u{1F3FC}' => Some(SkinTone::MediumLight),
+            '\u{1F3FD}' => Some(SkinTone::Medium),
+            '\u{1F3FE}' => Some(SkinTone::MediumDark),
+            '\u{1F3FF}' => Some(SkinTone::Dark),
+            _ => None,
+        }
+    }
+}
+
+/// Category name of the classic text emoticons
+pub const CLASSIC_CATEGORY: &str = "Classic";
+
+/// Classic text emoticons and kaomoji
+///
+/// The larger kaomoji library by mood lives in `data/kaomoji.txt`.
+pub const CLASSIC_EMOTICONS: &[&str] = &[
+    ":-)", ":)", ":(", ":-(", ";-)", ";)", ":-D", ":D", ":-P", ":P", ":-O", ":O", ":-|", ":|",
+    "<3", "</3", ":*", ":-*", "^_^", "^.^", "o_o", "O_O", "T_T", "ToT", ">_<", "-_-",
+    r"¯\_(ツ)_/¯", "(╯°□°）╯︵ ┻━┻", "(ಠ_ಠ)", "(◕‿◕)", "(づ｡◕‿‿◕｡)づ", "ʕ•ᴥ•ʔ",
+];
+
+pub static EMOTICONS: OnceLock<Vec<(&'static str, Vec<&'static str>)>> = OnceLock::new();
+static EMOJI_TABLE: OnceLock<&'static [EmojiEntry]> = OnceLock::new();
+static CACHE_INDEX: OnceLock<HashMap<&'static str, &'static EmojiEntry>> = OnceLock::new();
+static SKIN_TONE_INDEX: OnceLock<HashMap<String, Vec<&'static EmojiEntry>>> = OnceLock::new();
+static CUSTOM_EMOTICONS: OnceLock<Vec<CustomEmoticon>> = OnceLock::new();
+static CLASSIC: OnceLock<Vec<CustomEmoticon>> = OnceLock::new();
+static KAOMOJI: OnceLock<Vec<CustomEmoticon>> = OnceLock::new();
+static EXTRA_KEYWORDS: OnceLock<HashMap<&'static str, Vec<&'static str>>> = OnceLock::new();
+static BASE_FORMS: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+static FLAG_CODES: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+static QUALIFIED_FORMS: OnceLock<HashMap<Cow<'static, str>, &'static str>> = OnceLock::new();
+
+/// Use another emoji table instead of the built-in one, e.g. the data
+/// cache of `smile update-data`
+///
+/// Must be called before the emoji are first used; returns false if the
+/// built-in table is already in use.
+pub fn set_emoji_data(data: &'static [EmojiEntry]) -> bool {
+    EMOJI_TABLE.set(data).is_ok()
+}
+
+/// All entries of emoji-test.txt in CLDR order, from the data cache or the built-in table
+pub fn emoji_data() -> &'static [EmojiEntry] {
+    EMOJI_TABLE.get_or_init(|| EMOJI_DATA)
+}
+
+/// Get the emoji that are offered, by their Unicode group
+///
+/// Only fully-qualified emoji are offered; components and the unqualified
+/// forms are kept in the table for lookups only. Skin tone variants are
+/// reached through the base emoji. The groups are contiguous in the table,
+/// so the emoji are only borrowed from it.
+pub fn emoji_categories() -> Vec<(&'static str, Vec<&'static str>)> {
+    let mut categories: Vec<(&'static str, Vec<&'static str>)> = Vec::new();
+    let offered = emoji_data()
+        .iter()
+        .filter(|e| e.status == Qualification::FullyQualified)
+        .filter(|e| skin_tone_of(e.emoji) == Some(SkinTone::None));
+    for entry in offered {
+        match categories.last_mut() {
+            Some((group, emoji)) if *group == entry.group => emoji.push(entry.emoji),
+            _ => categories.push((entry.group, vec![entry.emoji])),
+        }
+    }
+    categories
+}
+
+/// Get the classic text emoticons
+pub fn classic() -> &'static [CustomEmoticon] {
+    CLASSIC.get_or_init(|| {
+        CLASSIC_EMOTICONS
+            .iter()
+            .map(|text| CustomEmoticon::new(text.to_string(), CLASSIC_CATEGORY.to_string(), Vec::new()))
+            .collect()
+    })
+}
+
+/// Get the built-in kaomoji library, one category per mood
+pub fn kaomoji() -> &'static [CustomEmoticon] {
+    KAOMOJI.get_or_init(|| {
+        let (kaomoji, errors) = packs::parse_pack(Path::new("data/kaomoji.txt"), include_str!("../data/kaomoji.txt"));
+        debug_assert!(errors.is_empty(), "Invalid kaomoji library: {:?}", errors);
+        kaomoji
+    })
+}
+
+/// Get the keywords of kaomoji, custom emoticons and other provider entries
+///
+/// The keywords are in lowercase, for the search.
+pub fn extra_keywords(emoticon: &str) -> &'static [&'static str] {
+    EXTRA_KEYWORDS
+        .get_or_init(|| {
+            let mut index: HashMap<&'static str, Vec<&'static str>> = HashMap::new();
+            let entries = providers::built_in().iter().flat_map(|p| p.emoticons());
+            for entry in entries.filter(|e| !e.keywords.is_empty()) {
+                let keywords = entry.search_terms().iter().map(String::as_str);
+                index.entry(entry.text.as_str()).or_default().extend(keywords);
+            }
+            index
+        })
+        .get(emoticon)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+/// Register the user-defined emoticons from custom.toml
+///
+/// Must be called before the first call to `get_emoticons()`; returns false
+/// if the emoticons were already set up without them.
+pub fn set_custom_emoticons(custom: Vec<CustomEmoticon>) -> bool {
+    CUSTOM_EMOTICONS.set(custom).is_ok()
+}
+
+/// Register the emoticons of custom.toml and the packs of a config directory
+///
+/// Problems are reported on stderr; the valid entries are still used.
+/// Must be called before the first call to `get_emoticons()`.
+pub fn use_custom_emoticons(config_dir: &Path) {
+    let (mut custom_emoticons, mut errors) = custom::load_custom(&config_dir.join("custom.toml"));
+    let (mut pack_emoticons, mut pack_errors) = packs::load_packs(&config_dir.join("packs"));
+    custom_emoticons.append(&mut pack_emoticons);
+    errors.append(&mut pack_errors);
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+    set_custom_emoticons(custom_emoticons);
+}
+
+/// Get the user-defined emoticons
+pub fn custom_emoticons() -> &'static [CustomEmoticon] {
+    CUSTOM_EMOTICONS.get_or_init(Vec::new)
+}
+
+/// Get all categories with their emoticons
+///
+/// The built-in providers are merged in order: the emoji in the CLDR order
+/// of emoji-test.txt, the classic emoticons, Gitmoji, the kaomoji moods and
+/// the custom categories. The user's `[layout]` is applied on top of this.
+/// The emoticons are borrowed from the tables of the providers.
+pub fn get_emoticons() -> &'static [(&'static str, Vec<&'static str>)] {
     EMOTICONS.get_or_init(|| {
-        let mut map = HashMap::new();
-        map.insert("Happy", vec![
-            "😀", "😃", "😄", "😁", "😆", "😊", "😇", "🙂", "🙃", "😉", "😌", "😍", "🥰", "😘"
-        ].iter().map(|s| s.to_string()).collect());
-        
-        map.insert("Laughing", vec![
-            "😂", "🤣", "😹", "😆", "😅", "🥲", "☺️", "😄", "😁"
-        ].iter().map(|s| s.to_string()).collect());
-        
-        map.insert("Sad", vec![
-            "😢", "😭", "😿", "😔", "😞", "😟", "😥", "😰", "😨", "😧", "😦"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Angry", vec![
-            "😠", "😡", "🤬", "😤", "😾", "💢"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Surprised", vec![
-            "😮", "😯", "😲", "😳", "🤯"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Love", vec![
-            "❤️", "💕", "💖", "💗", "💓", "💞", "💝", "💘", "💟", "♥️"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Gestures", vec![
-            "👍", "👎", "👌", "✌️", "🤞", "🤘", "🤙", "👏", "🙌", "👐", "🤲", "🤝", "🙏"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Faces", vec![
-            "😐", "😑", "😶", "🙄", "😏", "😣", "😥", "😮", "🤐", "😯", "😪", "😫", "🥱", "😴"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Cool", vec![
-            "😎", "🤓", "🧐", "😺", "😸", "😹", "😻", "😼", "😽", "🙀"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Symbols", vec![
-            "⭐", "✨", "🌟", "💫", "🔥", "💥", "💦", "💨", "✅", "❌", "⚡", "🌈"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Objects", vec![
-            "🎉", "🎊", "🎈", "🎁", "🏆", "🥇", "🥈", "🥉", "🏅", "🎖️"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Animals", vec![
-            "🐶", "🐱", "🐭", "🐹", "🐰", "🦊", "🐻", "🐼", "🐨", "🐯", "🦁", "🐮", "🐷", "🐸", "🐵"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Food", vec![
-            "🍕", "🍔", "🍟", "🌭", "🍿", "🧂", "🍰", "🎂", "🍩", "🍪", "🍫", "🍬", "🍭", "☕", "🍵"
-        ].iter().map(|s| s.to_string()).collect());
-        map.insert("Classic", vec![
-            ":-)", ":)", ":(", ":-(", ";-)", ";)", ":-D", ":D", ":-P", ":P", ":-O", ":O", ":-|", ":|",
-            "<3", "</3", ":*", ":-*", "^_^", "^.^", "o_o", "O_O", "T_T", "ToT", ">_<", "-_-",
-            r"¯\_(ツ)_/¯", "(╯°□°）╯︵ ┻━┻", "(ಠ_ಠ)", "(◕‿◕)", "(づ｡◕‿‿◕｡)づ", "ʕ•ᴥ•ʔ"
-        ].iter().map(|s| s.to_string()).collect());
-        map
+        let mut categories: Vec<(&'static str, Vec<&'static str>)> = Vec::new();
+        for provider in providers::built_in() {
+            for (name, emoticons) in provider.categories() {
+                match categories.iter_mut().find(|(category, _)| category.eq_ignore_ascii_case(name)) {
+                    Some((_, entries)) => {
+                        for emoticon in emoticons {
+                            if !entries.contains(&emoticon) {
+                                entries.push(emoticon);
+                            }
+                        }
+                    }
+                    None => categories.push((name, emoticons)),
+                }
+            }
+        }
+        categories
     })
 }
+
+/// Add custom or library emoticons to their categories, creating new categories as needed
+pub fn merge_custom(categories: &mut Vec<(&'static str, Vec<&'static str>)>, custom: &'static [CustomEmoticon]) {
+    for emoticon in custom {
+        let index = match categories
+            .iter()
+            .position(|(name, _)| name.eq_ignore_ascii_case(&emoticon.category))
+        {
+            Some(index) => index,
+            None => {
+                categories.push((emoticon.category.as_str(), Vec::new()));
+                categories.len() - 1
+            }
+        };
+        let entries = &mut categories[index].1;
+        if !entries.contains(&emoticon.text.as_str()) {
+            entries.push(emoticon.text.as_str());
+        }
+    }
+}
+
+/// Get the emoticons of a single category
+#[allow(dead_code)]
+pub fn get_category(name: &str) -> Option<&'static [&'static str]> {
+    get_emoticons()
+        .iter()
+        .find(|(category, _)| *category == name)
+        .map(|(_, emoticons)| emoticons.as_slice())
+}
+
+/// Look up the Unicode metadata of an emoji, in any qualification status
+///
+/// The built-in table has a perfect hash index; the data cache of `smile
+/// update-data` is indexed once, at its first lookup.
+pub fn lookup(emoji: &str) -> Option<&'static EmojiEntry> {
+    let data = emoji_data();
+    if std::ptr::eq(data, EMOJI_DATA) {
+        EMOJI_INDEX.get(emoji).map(|&position| &EMOJI_DATA[position])
+    } else {
+        CACHE_INDEX
+            .get_or_init(|| data.iter().map(|e| (e.emoji, e)).collect())
+            .get(emoji)
+            .copied()
+    }
+}
+
+/// Fully-qualified form of an emoji as shown in the picker, e.g. "❤️" for "❤"
+///
+/// Returns None for text that isn't an emoji. The fully-qualified emoji are
+/// indexed by their form without variation selectors at the first call.
+pub fn fully_qualified(emoji: &str) -> Option<&'static str> {
+    let forms = QUALIFIED_FORMS.get_or_init(|| {
+        let mut forms = HashMap::new();
+        for entry in emoji_data().iter().filter(|e| e.status == Qualification::FullyQualified) {
+            forms.entry(without_variation_selectors(entry.emoji)).or_insert(entry.emoji);
+        }
+        forms
+    });
+    forms.get(without_variation_selectors(emoji).as_ref()).copied()
+}
+
+/// An emoji without variation selectors, e.g. "❤" for "❤️", the way the
+/// CLDR, shortcode and Gitmoji tables write it
+///
+/// The emoji of the table are stripped once, at the first call, so the
+/// search allocates nothing for them.
+pub fn without_variation_selectors(emoji: &str) -> Cow<'_, str> {
+    if !emoji.contains('\u{FE0F}') {
+        return Cow::Borrowed(emoji);
+    }
+    let forms = BASE_FORMS.get_or_init(|| {
+        emoji_data()
+            .iter()
+            .filter(|e| e.emoji.contains('\u{FE0F}'))
+            .map(|e| (e.emoji, e.emoji.replace('\u{FE0F}', "")))
+            .collect()
+    });
+    match forms.get(emoji) {
+        Some(form) => Cow::Borrowed(form),
+        None => Cow::Owned(emoji.replace('\u{FE0F}', "")),
+    }
+}
+
+/// Format the code points of an emoji, e.g. "U+1F469 U+200D U+1F4BB"
+pub fn format_codepoints(emoji: &str) -> String {
+    emoji
+        .chars()
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// ISO 3166 code of a flag emoji, e.g. "DE" for 🇩🇪 and "GB-SCT" for 🏴󠁧󠁢󠁳󠁣󠁴󠁿
+///
+/// Country flags are pairs of regional indicator symbols, subdivision flags
+/// are a black flag followed by tag characters and a cancel tag.
+pub fn flag_code(emoji: &str) -> Option<String> {
+    const REGIONAL_INDICATOR_A: u32 = 0x1F1E6;
+    const TAG_CANCEL: char = '\u{E007F}';
+
+    let chars: Vec<char> = emoji.chars().collect();
+    match chars.as_slice() {
+        [first, second]
+            if (REGIONAL_INDICATOR_A..REGIONAL_INDICATOR_A + 26).contains(&(*first as u32))
+                && (REGIONAL_INDICATOR_A..REGIONAL_INDICATOR_A + 26).contains(&(*second as u32)) =>
+        {
+            let letter = |c: char| char::from(b'A' + (c as u32 - REGIONAL_INDICATOR_A) as u8);
+            Some([letter(*first), letter(*second)].iter().collect())
+        }
+        ['\u{1F3F4}', tags @ .., TAG_CANCEL] if tags.len() > 2 => {
+            // Tags mirror ASCII: "gbsct" is E0067 E0062 E0073 E0063 E0074
+            let tag: String = tags
+                .iter()
+                .map(|&c| char::from_u32(c as u32 - 0xE0000).filter(|c| c.is_ascii_alphanumeric()))
+                .collect::<Option<_>>()?;
+            let (country, subdivision) = tag.split_at(2);
+            Some(format!("{}-{}", country, subdivision).to_uppercase())
+        }
+        _ => None,
+    }
+}
+
+/// ISO 3166 code of a flag of the emoji table in lowercase, e.g. "gb-sct", for the search
+pub fn search_flag_code(emoji: &str) -> Option<&'static str> {
+    FLAG_CODES
+        .get_or_init(|| {
+            emoji_data()
+                .iter()
+                .filter_map(|e| Some((e.emoji, flag_code(e.emoji)?.to_lowercase())))
+                .collect()
+        })
+        .get(emoji)
+        .map(String::as_str)
+}
+
+/// Key shared by an emoji and all of its skin tone variants
+fn skin_tone_key(emoji: &str) -> String {
+    emoji
+        .chars()
+        .filter(|&c| SkinTone::from_modifier(c).is_none() && c != '\u{FE0F}')
+        .collect()
+}
+
+/// Fully-qualified emoji grouped by skin tone key, for emoji that have skin tone variants
+fn skin_tone_index() -> &'static HashMap<String, Vec<&'static EmojiEntry>> {
+    SKIN_TONE_INDEX.get_or_init(|| {
+        let mut index: HashMap<String, Vec<&'static EmojiEntry>> = HashMap::new();
+        for entry in emoji_data().iter().filter(|e| e.status == Qualification::FullyQualified) {
+            index.entry(skin_tone_key(entry.emoji)).or_default().push(entry);
+        }
+        index.retain(|_, entries| entries.len() > 1);
+        index
+    })
+}
+
+/// Skin tone of an emoji, or None if it mixes several tones
+pub fn skin_tone_of(emoji: &str) -> Option<SkinTone> {
+    let mut tones = emoji.chars().filter_map(SkinTone::from_modifier);
+    match tones.next() {
+        None => Some(SkinTone::None),
+        Some(first) => tones.all(|t| t == first).then_some(first),
+    }
+}
+
+/// Check if an emoji can be shown with a skin tone
+pub fn supports_skin_tone(emoji: &str) -> bool {
+    skin_tone_index().contains_key(&skin_tone_key(emoji))
+}
+
+/// Get the variant of an emoji with the given skin tone
+///
+/// Works from the base emoji as well as from any of its variants.
+/// Multi-person emoji get the same tone for every person.
+pub fn with_skin_tone(emoji: &str, tone: SkinTone) -> Option<&'static str> {
+    skin_tone_index()
+        .get(&skin_tone_key(emoji))?
+        .iter()
+        .find(|e| skin_tone_of(e.emoji) == Some(tone))
+        .map(|e| e.emoji)
+}
+
+/// Get all skin tone variants of an emoji, starting with the one without modifier
+pub fn skin_tone_variants(emoji: &str) -> Vec<&'static str> {
+    SkinTone::ALL.iter().filter_map(|&tone| with_skin_tone(emoji, tone)).collect()
+}
@@ -0,0 +1,214 @@
+//! Saved emoticon sequences from ~/.smile/favorites.toml
+//!
+//! ```toml
+//! [[favorite]]
+//! text = "🎉🎊🥳"
+//! name = "Party"
+//! keywords = ["celebrate", "congrats"] # optional
+//! ```
+//!
+//! A favorite is typed, searched and remembered in the recents as a whole.
+//! The picker appends new favorites to the file, so comments and the order
+//! of the existing entries are kept.
+
+use crate::custom::{self, CustomError};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A saved sequence of emoticons
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Favorite {
+    /// Text that is typed, e.g. "🎉🎊🥳"
+    pub text: String,
+    /// Name shown in the tooltip and searched
+    pub name: String,
+    /// Search keywords
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+impl Favorite {
+    /// Check if a lowercase search query matches the name, keywords or text
+    pub fn matches(&self, query: &str) -> bool {
+        self.text.to_lowercase().contains(query)
+            || self.name.to_lowercase().contains(query)
+            || self.keywords.iter().any(|k| k.to_lowercase().contains(query))
+    }
+
+    /// The `[[favorite]]` entry of this favorite
+    fn to_toml(&self) -> String {
+        let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+        let mut entry = format!("[[favorite]]\ntext = {}\nname = {}\n", quote(&self.text), quote(&self.name));
+        if !self.keywords.is_empty() {
+            let keywords: Vec<String> = self.keywords.iter().map(|k| quote(k)).collect();
+            entry.push_str(&format!("keywords = [{}]\n", keywords.join(", ")));
+        }
+        entry
+    }
+}
+
+/// Layout of favorites.toml; entries are kept as raw values so that a
+/// single invalid entry doesn't reject the whole file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FavoritesFile {
+    #[serde(default)]
+    favorite: Vec<toml::Spanned<toml::Value>>,
+}
+
+/// Load the favorites from a file
+///
+/// A missing file is not an error, it just means there are no favorites.
+pub fn load_favorites(path: &Path) -> (Vec<Favorite>, Vec<CustomError>) {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_favorites(path, &content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (Vec::new(), Vec::new()),
+        Err(e) => (
+            Vec::new(),
+            vec![CustomError {
+                file: path.to_path_buf(),
+                line: 1,
+                message: format!("Failed to read file: {}", e),
+            }],
+        ),
+    }
+}
+
+/// Parse and validate the content of a favorites file
+pub fn parse_favorites(path: &Path, content: &str) -> (Vec<Favorite>, Vec<CustomError>) {
+    let line_of = |offset: usize| content[..offset.min(content.len())].matches('\n').count() + 1;
+    let error = |line: usize, message: String| CustomError {
+        file: path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let file: FavoritesFile = match toml::from_str(content) {
+        Ok(file) => file,
+        Err(e) => {
+            let line = e.span().map(|span| line_of(span.start)).unwrap_or(1);
+            return (Vec::new(), vec![error(line, e.message().to_string())]);
+        }
+    };
+
+    let mut favorites: Vec<Favorite> = Vec::new();
+    let mut errors = Vec::new();
+
+    for spanned in file.favorite {
+        let line = line_of(spanned.span().start);
+        let favorite = match spanned.into_inner().try_into::<Favorite>() {
+            Ok(favorite) => favorite,
+            Err(e) => {
+                errors.push(error(line, format!("Invalid favorite: {}", e.message())));
+                continue;
+            }
+        };
+        match validate(&favorite, &favorites) {
+            Some(message) => errors.push(error(line, message)),
+            None => favorites.push(favorite),
+        }
+    }
+
+    (favorites, errors)
+}
+
+/// Check a favorite against the existing ones
+///
+/// Returns a description of the problem, if any.
+pub fn validate(favorite: &Favorite, existing: &[Favorite]) -> Option<String> {
+    if favorite.text.trim().is_empty() {
+        Some("Favorite text is empty".to_string())
+    } else if favorite.text.chars().any(char::is_control) {
+        Some(format!("Favorite text '{}' contains control characters", favorite.text.escape_debug()))
+    } else if favorite.name.trim().is_empty() {
+        Some(format!("Name of '{}' is empty", favorite.text))
+    } else if favorite.keywords.iter().any(|k| k.trim().is_empty()) {
+        Some(format!("Keywords of '{}' contain an empty keyword", favorite.text))
+    } else {
+        existing
+            .iter()
+            .find(|f| f.text == favorite.text)
+            .map(|other| format!("'{}' is already saved as '{}'", favorite.text, other.name))
+    }
+}
+
+/// Append a favorite to the file, creating it if needed
+pub fn append_favorite(path: &Path, favorite: &Favorite) -> io::Result<()> {
+    custom::append_entry(path, &favorite.to_toml())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> (Vec<Favorite>, Vec<CustomError>) {
+        parse_favorites(Path::new("favorites.toml"), content)
+    }
+
+    fn party() -> Favorite {
+        Favorite {
+            text: "🎉🎊🥳".to_string(),
+            name: "Party".to_string(),
+            keywords: vec!["celebrate".to_string(), "say \"congrats\"".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_parse_favorites() {
+        let (favorites, errors) = parse(
+            r#"
+[[favorite]]
+text = "👀➡️"
+name = "Look over there"
+
+[[favorite]]
+text = "  "
+name = "Empty"
+
+[[favorite]]
+text = "👀➡️"
+name = "Twice"
+
+[[favorite]]
+text = "🙂"
+"#,
+        );
+        assert_eq!(favorites.len(), 1);
+        assert!(favorites[0].keywords.is_empty());
+
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![6, 10, 14]);
+        assert!(errors[1].message.contains("already saved as 'Look over there'"));
+        assert!(errors[2].message.contains("name"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let favorite = party();
+        assert!(favorite.matches("party"));
+        assert!(favorite.matches("celeb"));
+        assert!(favorite.matches("🥳"));
+        assert!(!favorite.matches("sad"));
+    }
+
+    #[test]
+    fn test_append_favorite() {
+        let dir = std::env::temp_dir().join(format!("smile_favorites_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("favorites.toml");
+        fs::write(&path, "# My favorites\n[[favorite]]\ntext = \"👀➡️\"\nname = \"Look\"").unwrap();
+
+        append_favorite(&path, &party()).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# My favorites\n"));
+        let (favorites, errors) = load_favorites(&path);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(favorites.len(), 2);
+        assert_eq!(favorites[1], party());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
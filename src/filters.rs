@@ -0,0 +1,228 @@
+//! Text filters of `smile emojize`, `smile demojize` and `smile describe`
+//!
+//! The filters work on any text, e.g. a line of a chat log or a commit
+//! message, and leave everything that isn't an emoji, a shortcode or a
+//! smiley as it is. Emoji are found in the same table as in the picker,
+//! the longest sequence first, so "👩🏽‍💻" is one emoji and not three.
+
+use crate::annotations;
+use crate::emoticons::{self, EmojiEntry, Qualification};
+use crate::shortcodes::{self, ShortcodeSet};
+use std::sync::OnceLock;
+
+/// ASCII smileys of the Classic category and the emoji they stand for
+///
+/// The first smiley of an emoji is the one `demojize --smileys` writes.
+pub const SMILEYS: &[(&str, &str)] = &[
+    (":-)", "🙂"),
+    (":)", "🙂"),
+    (":-(", "🙁"),
+    (":(", "🙁"),
+    (";-)", "😉"),
+    (";)", "😉"),
+    (":-D", "😃"),
+    (":D", "😃"),
+    (":-P", "😛"),
+    (":P", "😛"),
+    (":-O", "😮"),
+    (":O", "😮"),
+    (":-|", "😐"),
+    (":|", "😐"),
+    ("<3", "❤️"),
+    ("</3", "💔"),
+    (":-*", "😘"),
+    (":*", "😘"),
+    ("^_^", "😊"),
+    ("^.^", "😊"),
+    ("O_O", "😳"),
+    ("o_o", "😳"),
+    ("T_T", "😭"),
+    ("ToT", "😭"),
+    (">_<", "😣"),
+    ("-_-", "😑"),
+];
+
+static MAX_EMOJI_CHARS: OnceLock<usize> = OnceLock::new();
+
+/// Emoji at the start of a text, with its length in bytes
+///
+/// Components like a lone skin tone and unqualified single characters like
+/// "©" or "☺" are left alone, as they are text unless written as emoji.
+pub fn emoji_at(text: &str) -> Option<(&'static EmojiEntry, usize)> {
+    let first = text.chars().next()?;
+    if first.is_ascii() && !matches!(first, '#' | '*' | '0'..='9') {
+        return None;
+    }
+    let max = *MAX_EMOJI_CHARS.get_or_init(|| {
+        emoticons::emoji_data()
+            .iter()
+            .map(|e| e.emoji.chars().count())
+            .max()
+            .unwrap_or(1)
+    });
+    let ends: Vec<usize> = text.char_indices().map(|(i, c)| i + c.len_utf8()).take(max).collect();
+    ends.into_iter().rev().find_map(|end| {
+        let entry = emoticons::lookup(&text[..end])?;
+        let plain_text = match entry.status {
+            Qualification::Component => true,
+            Qualification::Unqualified => entry.emoji.chars().count() == 1,
+            Qualification::FullyQualified | Qualification::MinimallyQualified => false,
+        };
+        (!plain_text).then_some((entry, end))
+    })
+}
+
+/// Replace every emoji of a text with what `replace` returns for it
+fn map_emoji(text: &str, replace: impl Fn(&'static EmojiEntry, &str) -> String) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match emoji_at(rest) {
+            Some((entry, len)) => {
+                output.push_str(&replace(entry, &rest[..len]));
+                rest = &rest[len..];
+            }
+            None => {
+                output.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    output
+}
+
+/// Emoji of a shortcode, in the given set first and then in the others
+fn emoji_for_shortcode(set: ShortcodeSet, code: &str) -> Option<&'static str> {
+    std::iter::once(set)
+        .chain(ShortcodeSet::ALL.into_iter().filter(|&other| other != set))
+        .find_map(|set| shortcodes::emoji_for(set, code))
+}
+
+/// Length in bytes of a `:shortcode:` at the start of a text, if it is one
+fn shortcode_at(text: &str) -> Option<usize> {
+    let code = text.strip_prefix(':')?;
+    let end = code.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+')))?;
+    (end > 0 && code[end..].starts_with(':')).then_some(end + 2)
+}
+
+/// Smiley at the start of a text that is followed by a word boundary
+fn smiley_at(text: &str) -> Option<(&'static str, &'static str)> {
+    SMILEYS
+        .iter()
+        .filter(|(smiley, _)| {
+            text.strip_prefix(smiley)
+                .is_some_and(|rest| rest.chars().next().is_none_or(|c| c.is_whitespace() || ".,!?;)".contains(c)))
+        })
+        .max_by_key(|(smiley, _)| smiley.len())
+        .copied()
+}
+
+/// Turn `:shortcodes:` and ASCII smileys into emoji
+///
+/// Shortcodes are looked up in `set` first, then in the other sets. Smileys
+/// and shortcodes have to start a word, so "10:30:00" and "http://" stay.
+pub fn emojize(text: &str, set: ShortcodeSet, smileys: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut word_start = true;
+    while let Some(c) = rest.chars().next() {
+        if word_start {
+            if let Some(len) = shortcode_at(rest) {
+                if let Some(emoji) = emoji_for_shortcode(set, &rest[..len]) {
+                    output.push_str(emoji);
+                    rest = &rest[len..];
+                    word_start = true;
+                    continue;
+                }
+            }
+            if let Some((smiley, emoji)) = smiley_at(rest).filter(|_| smileys) {
+                output.push_str(emoji);
+                rest = &rest[smiley.len()..];
+                word_start = true;
+                continue;
+            }
+        }
+        output.push(c);
+        rest = &rest[c.len_utf8()..];
+        word_start = !c.is_alphanumeric();
+    }
+    output
+}
+
+/// Turn emoji into `:shortcodes:`, or into ASCII smileys with `smileys`
+///
+/// Emoji without a shortcode in the set are kept.
+pub fn demojize(text: &str, set: ShortcodeSet, smileys: bool) -> String {
+    map_emoji(text, |entry, emoji| {
+        let smiley = smileys
+            .then(|| {
+                let qualified = emoticons::fully_qualified(entry.emoji).unwrap_or(entry.emoji);
+                SMILEYS.iter().find(|(_, e)| *e == qualified).map(|(smiley, _)| smiley.to_string())
+            })
+            .flatten();
+        smiley
+            .or_else(|| shortcodes::shortcode(set, entry.emoji))
+            .unwrap_or_else(|| emoji.to_string())
+    })
+}
+
+/// Write the name after every emoji, e.g. "🎉 (party popper)"
+///
+/// The name is the CLDR name in the first of `locales` that has one, or the
+/// English name of the Unicode data.
+pub fn describe(text: &str, locales: &[String]) -> String {
+    map_emoji(text, |entry, emoji| {
+        let name = locales
+            .iter()
+            .find_map(|locale| annotations::get(locale, entry.emoji))
+            .map(|annotation| annotation.name)
+            .unwrap_or(entry.name);
+        format!("{} ({})", emoji, name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smileys_are_classic() {
+        for (smiley, emoji) in SMILEYS {
+            assert!(emoticons::CLASSIC_EMOTICONS.contains(smiley), "{}", smiley);
+            assert!(emoticons::lookup(emoji).is_some(), "{}", emoji);
+        }
+    }
+
+    #[test]
+    fn test_emojize() {
+        let set = ShortcodeSet::Github;
+        assert_eq!(emojize("Shipped :tada: :-)", set, true), "Shipped 🎉 🙂");
+        assert_eq!(emojize(":+1: (<3)", set, true), "👍 (❤️)");
+        assert_eq!(emojize(":tada::+1:", set, true), "🎉👍");
+        assert_eq!(emojize("Ok :D, see you", set, true), "Ok 😃, see you");
+        assert_eq!(emojize("Ok :D", set, false), "Ok :D");
+        // Only in the Slack table
+        assert_eq!(emojize(":face_with_monocle:", set, true), "🧐");
+        // Not at the start of a word, or not a shortcode at all
+        assert_eq!(emojize("at 10:30:00, a:Pb, :nope: http://x", set, true), "at 10:30:00, a:Pb, :nope: http://x");
+        assert_eq!(emojize(":Pfoo :tada", set, true), ":Pfoo :tada");
+    }
+
+    #[test]
+    fn test_demojize() {
+        let set = ShortcodeSet::Github;
+        assert_eq!(demojize("Shipped 🎉🙂\n", set, false), "Shipped :tada::slightly_smiling_face:\n");
+        assert_eq!(demojize("Shipped 🎉🙂", set, true), "Shipped :tada::-)");
+        assert_eq!(demojize("❤️ ❤", set, true), "<3 ❤");
+        assert_eq!(demojize("👍🏽", ShortcodeSet::Slack, false), ":+1::skin-tone-4:");
+        assert_eq!(demojize("© 2024 #1", set, false), "© 2024 #1");
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(describe("Done 🎉!", &[]), "Done 🎉 (party popper)!");
+        assert_eq!(describe("👩🏽‍💻", &[]), "👩🏽‍💻 (woman technologist: medium skin tone)");
+        assert_eq!(describe("1️⃣ and 1", &[]), "1️⃣ (keycap: 1) and 1");
+        assert_eq!(describe("no emoji ☺ ©", &[]), "no emoji ☺ ©");
+    }
+}
@@ -0,0 +1,189 @@
+//! Gitmoji - emoji for commit messages, e.g. 🐛 `:bug:` "Fix a bug."
+//!
+//! The table follows the list published at <https://gitmoji.dev>; it was
+//! typed in by hand rather than generated from the upstream gitmojis.json.
+//! The emoji are written in their fully-qualified form so that they match
+//! the picker; gitmoji.dev itself adds a variation selector to some of them.
+//! The whole pack can be switched off with `gitmoji = "off"` in the settings.
+
+use crate::custom::CustomEmoticon;
+use crate::emoticons;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Category name of the Gitmoji pack
+pub const GITMOJI_CATEGORY: &str = "Gitmoji";
+
+/// A Gitmoji with its meaning
+#[derive(Debug, PartialEq)]
+pub struct Gitmoji {
+    pub emoji: &'static str,
+    /// Code with colons, e.g. ":bug:"
+    pub code: &'static str,
+    pub description: &'static str,
+}
+
+macro_rules! gitmoji {
+    ($($emoji:literal $code:literal $description:literal,)*) => {
+        &[$(Gitmoji { emoji: $emoji, code: $code, description: $description },)*]
+    };
+}
+
+/// All Gitmoji in the order of gitmoji.dev
+pub const GITMOJI: &[Gitmoji] = gitmoji![
+    "🎨" ":art:" "Improve structure / format of the code.",
+    "⚡" ":zap:" "Improve performance.",
+    "🔥" ":fire:" "Remove code or files.",
+    "🐛" ":bug:" "Fix a bug.",
+    "🚑" ":ambulance:" "Critical hotfix.",
+    "✨" ":sparkles:" "Introduce new features.",
+    "📝" ":memo:" "Add or update documentation.",
+    "🚀" ":rocket:" "Deploy stuff.",
+    "💄" ":lipstick:" "Add or update the UI and style files.",
+    "🎉" ":tada:" "Begin a project.",
+    "✅" ":white_check_mark:" "Add, update, or pass tests.",
+    "🔒" ":lock:" "Fix security or privacy issues.",
+    "🔐" ":closed_lock_with_key:" "Add or update secrets.",
+    "🔖" ":bookmark:" "Release / Version tags.",
+    "🚨" ":rotating_light:" "Fix compiler / linter warnings.",
+    "🚧" ":construction:" "Work in progress.",
+    "💚" ":green_heart:" "Fix CI Build.",
+    "⬇️" ":arrow_down:" "Downgrade dependencies.",
+    "⬆️" ":arrow_up:" "Upgrade dependencies.",
+    "📌" ":pushpin:" "Pin dependencies to specific versions.",
+    "👷" ":construction_worker:" "Add or update CI build system.",
+    "📈" ":chart_with_upwards_trend:" "Add or update analytics or track code.",
+    "♻️" ":recycle:" "Refactor code.",
+    "➕" ":heavy_plus_sign:" "Add a dependency.",
+    "➖" ":heavy_minus_sign:" "Remove a dependency.",
+    "🔧" ":wrench:" "Add or update configuration files.",
+    "🔨" ":hammer:" "Add or update development scripts.",
+    "🌐" ":globe_with_meridians:" "Internationalization and localization.",
+    "✏️" ":pencil2:" "Fix typos.",
+    "💩" ":poop:" "Write bad code that needs to be improved.",
+    "⏪" ":rewind:" "Revert changes.",
+    "🔀" ":twisted_rightwards_arrows:" "Merge branches.",
+    "📦" ":package:" "Add or update compiled files or packages.",
+    "👽" ":alien:" "Update code due to external API changes.",
+    "🚚" ":truck:" "Move or rename resources (e.g.: files, paths, routes).",
+    "📄" ":page_facing_up:" "Add or update license.",
+    "💥" ":boom:" "Introduce breaking changes.",
+    "🍱" ":bento:" "Add or update assets.",
+    "♿" ":wheelchair:" "Improve accessibility.",
+    "💡" ":bulb:" "Add or update comments in source code.",
+    "🍻" ":beers:" "Write code drunkenly.",
+    "💬" ":speech_balloon:" "Add or update text and literals.",
+    "🗃️" ":card_file_box:" "Perform database related changes.",
+    "🔊" ":loud_sound:" "Add or update logs.",
+    "🔇" ":mute:" "Remove logs.",
+    "👥" ":busts_in_silhouette:" "Add or update contributor(s).",
+    "🚸" ":children_crossing:" "Improve user experience / usability.",
+    "🏗️" ":building_construction:" "Make architectural changes.",
+    "📱" ":iphone:" "Work on responsive design.",
+    "🤡" ":clown_face:" "Mock things.",
+    "🥚" ":egg:" "Add or update an easter egg.",
+    "🙈" ":see_no_evil:" "Add or update a .gitignore file.",
+    "📸" ":camera_flash:" "Add or update snapshots.",
+    "⚗️" ":alembic:" "Perform experiments.",
+    "🔍" ":mag:" "Improve SEO.",
+    "🏷️" ":label:" "Add or update types.",
+    "🌱" ":seedling:" "Add or update seed files.",
+    "🚩" ":triangular_flag_on_post:" "Add, update, or remove feature flags.",
+    "🥅" ":goal_net:" "Catch errors.",
+    "💫" ":dizzy:" "Add or update animations and transitions.",
+    "🗑️" ":wastebasket:" "Deprecate code that needs to be cleaned up.",
+    "🛂" ":passport_control:" "Work on code related to authorization, roles and permissions.",
+    "🩹" ":adhesive_bandage:" "Simple fix for a non-critical issue.",
+    "🧐" ":monocle_face:" "Data exploration/inspection.",
+    "⚰️" ":coffin:" "Remove dead code.",
+    "🧪" ":test_tube:" "Add a failing test.",
+    "👔" ":necktie:" "Add or update business logic.",
+    "🩺" ":stethoscope:" "Add or update healthcheck.",
+    "🧱" ":bricks:" "Infrastructure related changes.",
+    "🧑‍💻" ":technologist:" "Improve developer experience.",
+    "💸" ":money_with_wings:" "Add sponsorships or money related infrastructure.",
+    "🧵" ":thread:" "Add or update code related to multithreading or concurrency.",
+    "🦺" ":safety_vest:" "Add or update code related to validation.",
+    "✈️" ":airplane:" "Improve offline support.",
+];
+
+static EMOTICONS: OnceLock<Vec<CustomEmoticon>> = OnceLock::new();
+/// Gitmoji by their emoji without variation selectors, with their meaning in lowercase
+static INDEX: OnceLock<HashMap<String, (&'static Gitmoji, String)>> = OnceLock::new();
+
+fn indexed(emoji: &str) -> Option<&'static (&'static Gitmoji, String)> {
+    let index = INDEX.get_or_init(|| {
+        GITMOJI
+            .iter()
+            .map(|g| (g.emoji.replace('\u{FE0F}', ""), (g, g.description.to_lowercase())))
+            .collect()
+    });
+    index.get(emoticons::without_variation_selectors(emoji).as_ref())
+}
+
+/// Look up the Gitmoji of an emoji, ignoring variation selectors
+pub fn lookup(emoji: &str) -> Option<&'static Gitmoji> {
+    indexed(emoji).map(|(gitmoji, _)| *gitmoji)
+}
+
+/// Whether an emoji is a Gitmoji whose code or meaning contains a lowercase search
+pub fn matches(emoji: &str, query_lower: &str) -> bool {
+    indexed(emoji).is_some_and(|(gitmoji, description)| {
+        gitmoji.code.contains(query_lower) || description.contains(query_lower)
+    })
+}
+
+/// The Gitmoji as library emoticons
+///
+/// They are searched by code and meaning through `lookup()`, so that the
+/// search follows the `gitmoji` setting.
+pub fn emoticons() -> &'static [CustomEmoticon] {
+    EMOTICONS.get_or_init(|| {
+        GITMOJI
+            .iter()
+            .map(|g| CustomEmoticon::new(g.emoji.to_string(), GITMOJI_CATEGORY.to_string(), Vec::new()))
+            .collect()
+    })
+}
+
+/// Tooltip line of a Gitmoji, e.g. ":bug: Fix a bug."
+pub fn tooltip(gitmoji: &Gitmoji) -> String {
+    format!("{} {}", gitmoji.code, gitmoji.description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emoticons::{self, Qualification};
+
+    #[test]
+    fn test_lookup() {
+        let bug = lookup("🐛").unwrap();
+        assert_eq!(bug.code, ":bug:");
+        assert_eq!(tooltip(bug), ":bug: Fix a bug.");
+        // gitmoji.dev writes ⚡ with a variation selector
+        assert_eq!(lookup("⚡\u{FE0F}").unwrap().code, ":zap:");
+        assert_eq!(lookup("♻").unwrap().code, ":recycle:");
+        assert!(lookup("😀").is_none());
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("🐛", ":bug"));
+        assert!(matches("🐛", "fix a bug"));
+        assert!(matches("♻️", "refactor"));
+        assert!(!matches("😀", "grin"));
+    }
+
+    #[test]
+    fn test_table() {
+        let mut codes = std::collections::HashSet::new();
+        for gitmoji in GITMOJI {
+            let entry = emoticons::lookup(gitmoji.emoji).unwrap_or_else(|| panic!("{} is not an emoji", gitmoji.emoji));
+            assert_eq!(entry.status, Qualification::FullyQualified, "{}", gitmoji.emoji);
+            assert!(gitmoji.code.starts_with(':') && gitmoji.code.ends_with(':'), "{}", gitmoji.code);
+            assert!(codes.insert(gitmoji.code), "duplicate code {}", gitmoji.code);
+        }
+        assert_eq!(emoticons().len(), GITMOJI.len());
+    }
+}
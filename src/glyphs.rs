@@ -0,0 +1,111 @@
+//! Glyph coverage of the installed fonts
+//!
+//! Emoji newer than the installed emoji font are drawn as boxes ("tofu").
+//! Pango reports those as unknown glyphs when laying out the text, using the
+//! same fontconfig fallback as the emoticon buttons. ZWJ sequences, keycaps
+//! and flags the font has no ligature for are drawn as their parts instead,
+//! e.g. 👨 💻 for 👨‍💻, so an emoji that takes more than one glyph counts as
+//! unsupported as well.
+
+use crate::emoticons;
+use gtk::pango;
+use gtk::pango::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Fingerprint of the font configuration and the checked emoticons
+///
+/// Changes when fonts are installed, removed or updated, when the default
+/// font changes and when emoticons are added, so a cached coverage is only
+/// used for exactly the setup it was computed for. An updated emoji font
+/// keeps its family name, so the font files are part of the fingerprint.
+pub fn fingerprint<'a>(context: &pango::Context, emoticons: impl Iterator<Item = &'a str>) -> String {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    pango::version_string().as_str().hash(&mut hasher);
+
+    if let Some(description) = context.font_description() {
+        description.to_str().as_str().hash(&mut hasher);
+    }
+    let mut families: Vec<String> = context.list_families().iter().map(|f| f.name().to_string()).collect();
+    families.sort();
+    families.hash(&mut hasher);
+    for dir in font_dirs() {
+        hash_font_files(&dir, &mut hasher);
+    }
+
+    for emoticon in emoticons {
+        emoticon.hash(&mut hasher);
+    }
+
+    format!("{:016x}", hasher.finish())
+}
+
+/// Directories fontconfig reads fonts from by default
+fn font_dirs() -> Vec<PathBuf> {
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    let mut dirs: Vec<PathBuf> = std::env::split_paths(&data_dirs).map(|dir| dir.join("fonts")).collect();
+    dirs.extend(dirs::data_dir().map(|dir| dir.join("fonts")));
+    dirs.extend(dirs::home_dir().map(|dir| dir.join(".fonts")));
+    dirs
+}
+
+/// Hash the paths, sizes and modification times of the font files below a directory
+fn hash_font_files(dir: &Path, hasher: &mut DefaultHasher) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        // Follows symbolic links, which distributions use for font directories
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            hash_font_files(&path, hasher);
+        } else {
+            path.hash(hasher);
+            metadata.len().hash(hasher);
+            metadata.modified().ok().hash(hasher);
+        }
+    }
+}
+
+/// Find the emoticons that the fonts of a Pango context can't render
+pub fn unsupported<'a>(context: &pango::Context, emoticons: impl Iterator<Item = &'a str>) -> HashSet<String> {
+    let layout = pango::Layout::new(context);
+    emoticons
+        .filter(|emoticon| {
+            layout.set_text(emoticon);
+            layout.unknown_glyphs_count() > 0 || (emoticons::lookup(emoticon).is_some() && visible_glyphs(&layout) > 1)
+        })
+        .map(|emoticon| emoticon.to_string())
+        .collect()
+}
+
+/// Number of glyphs of a layout that are drawn
+///
+/// Joiners and variation selectors are laid out as empty glyphs.
+fn visible_glyphs(layout: &pango::Layout) -> usize {
+    let Some(line) = layout.line_readonly(0) else {
+        return 0;
+    };
+    line.runs()
+        .iter()
+        .map(|run| {
+            run.glyph_string()
+                .glyph_info()
+                .iter()
+                .filter(|info| info.glyph() != pango::GLYPH_EMPTY && info.geometry().width() > 0)
+                .count()
+        })
+        .sum()
+}
@@ -0,0 +1,195 @@
+//! Custom image emoji from ~/.smile/images/
+//!
+//! Every image in the folder is an entry of the Images category, e.g. team
+//! logos or reaction images of a chat tool. They are searched by their file
+//! name and by keywords from `keywords.toml` in the same folder:
+//!
+//! ```toml
+//! "party-parrot.gif" = ["party", "celebrate"]
+//! "acme-logo.png" = ["company", "team"]
+//! ```
+//!
+//! Images can't be typed, so the picker puts them on the clipboard.
+
+use crate::custom::CustomError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Category of the image emoji
+pub const IMAGES_CATEGORY: &str = "Images";
+
+/// File with the keywords of the images
+pub const KEYWORDS_FILE: &str = "keywords.toml";
+
+/// Extensions of the images that are shown, in lowercase
+const EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp"];
+
+/// An image emoji
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageEmoji {
+    pub path: PathBuf,
+    /// File name without extension, e.g. "party-parrot"
+    pub name: String,
+    /// Search keywords
+    pub keywords: Vec<String>,
+}
+
+impl ImageEmoji {
+    /// Check if a lowercase search query matches the name or the keywords
+    ///
+    /// Dashes and underscores of the name match spaces, so "party parrot"
+    /// finds "party-parrot.gif".
+    pub fn matches(&self, query: &str) -> bool {
+        let name = self.name.to_lowercase();
+        name.contains(query)
+            || name.replace(['-', '_'], " ").contains(query)
+            || self.keywords.iter().any(|k| k.to_lowercase().contains(query))
+    }
+}
+
+/// Load the images of a folder, in file name order
+///
+/// A missing folder is not an error, it just means there are no images.
+pub fn load_images(dir: &Path) -> (Vec<ImageEmoji>, Vec<CustomError>) {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && is_image(p))
+            .collect(),
+        Err(_) => return (Vec::new(), Vec::new()),
+    };
+    files.sort();
+
+    let keywords_file = dir.join(KEYWORDS_FILE);
+    let (keywords, mut errors) = match fs::read_to_string(&keywords_file) {
+        Ok(content) => parse_keywords(&keywords_file, &content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (BTreeMap::new(), Vec::new()),
+        Err(e) => (
+            BTreeMap::new(),
+            vec![CustomError {
+                file: keywords_file.clone(),
+                line: 1,
+                message: format!("Failed to read file: {}", e),
+            }],
+        ),
+    };
+
+    let images: Vec<ImageEmoji> = files
+        .into_iter()
+        .map(|path| {
+            let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            ImageEmoji {
+                keywords: keywords.get(&file_name).map(|(_, k)| k.clone()).unwrap_or_default(),
+                path,
+                name,
+            }
+        })
+        .collect();
+
+    // Keywords of images that aren't there are most likely a typo
+    for (file_name, (line, _)) in &keywords {
+        if !images.iter().any(|image| image.path.file_name().is_some_and(|n| n == file_name.as_str())) {
+            errors.push(CustomError {
+                file: keywords_file.clone(),
+                line: *line,
+                message: format!("No image '{}' in {}", file_name, dir.display()),
+            });
+        }
+    }
+
+    (images, errors)
+}
+
+/// Check if a file has the extension of a supported image format
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Line and keywords of each file name in keywords.toml
+type Keywords = BTreeMap<String, (usize, Vec<String>)>;
+
+/// Parse the content of keywords.toml
+fn parse_keywords(path: &Path, content: &str) -> (Keywords, Vec<CustomError>) {
+    let line_of = |offset: usize| content[..offset.min(content.len())].matches('\n').count() + 1;
+    let error = |line: usize, message: String| CustomError {
+        file: path.to_path_buf(),
+        line,
+        message,
+    };
+
+    let table: BTreeMap<String, toml::Spanned<toml::Value>> = match toml::from_str(content) {
+        Ok(table) => table,
+        Err(e) => {
+            let line = e.span().map(|span| line_of(span.start)).unwrap_or(1);
+            return (BTreeMap::new(), vec![error(line, e.message().to_string())]);
+        }
+    };
+
+    let mut keywords = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (file_name, spanned) in table {
+        let line = line_of(spanned.span().start);
+        match spanned.into_inner().try_into::<Vec<String>>() {
+            Ok(terms) if terms.iter().any(|k| k.trim().is_empty()) => {
+                errors.push(error(line, format!("Keywords of '{}' contain an empty keyword", file_name)));
+            }
+            Ok(terms) => {
+                keywords.insert(file_name, (line, terms));
+            }
+            Err(_) => errors.push(error(line, format!("Keywords of '{}' are not a list of strings", file_name))),
+        }
+    }
+    (keywords, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_images() {
+        let dir = std::env::temp_dir().join(format!("smile_images_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for file in ["party-parrot.gif", "acme_logo.PNG", "notes.txt"] {
+            fs::write(dir.join(file), b"").unwrap();
+        }
+        fs::write(
+            dir.join(KEYWORDS_FILE),
+            "\"party-parrot.gif\" = [\"celebrate\"]\n\"missing.png\" = [\"gone\"]\n\"acme_logo.PNG\" = \"team\"\n",
+        )
+        .unwrap();
+
+        let (images, errors) = load_images(&dir);
+        let names: Vec<&str> = images.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["acme_logo", "party-parrot"]);
+        assert_eq!(images[1].keywords, vec!["celebrate"]);
+        assert!(images[0].keywords.is_empty());
+
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 2]);
+        assert!(errors[0].message.contains("not a list"));
+        assert!(errors[1].message.contains("No image 'missing.png'"));
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(load_images(&dir), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn test_matches() {
+        let image = ImageEmoji {
+            path: PathBuf::from("party-parrot.gif"),
+            name: "party-parrot".to_string(),
+            keywords: vec!["Celebrate".to_string()],
+        };
+        assert!(image.matches("parrot"));
+        assert!(image.matches("party parrot"));
+        assert!(image.matches("celeb"));
+        assert!(!image.matches("logo"));
+    }
+}
@@ -0,0 +1,371 @@
+//! Import keywords and history from other Linux emoji pickers
+//!
+//! `smile import --from <picker> <path>` reads the data and history files of
+//! rofimoji, Emote or splatmoji. The path is either a single file or a
+//! directory, whose files are recognized by their names:
+//!
+//! - rofimoji: data files (`*.csv`, `*.txt`) with a character, a space and
+//!   its description, optionally followed by `<small>(keyword, ...)</small>`;
+//!   the history is the `recent` file with one character per line.
+//! - Emote: emoji data in the emojibase JSON format (`*.json`); the history
+//!   is the `recent-emojis` key of `dconf dump /com/tomjwatson/Emote/` or
+//!   the output of `gsettings get com.tomjwatson.Emote recent-emojis`, saved
+//!   to any other file.
+//! - splatmoji: data files (`*.tsv`) with an emoticon, a tab and comma
+//!   separated keywords; the history is the `history` file with one emoticon
+//!   per line, optionally with a use count separated by a tab.
+//!
+//! Emoji are converted to the fully-qualified form that the picker shows.
+
+use crate::emoticons;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The picker to import from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Rofimoji,
+    Emote,
+    Splatmoji,
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rofimoji" => Ok(Source::Rofimoji),
+            "emote" => Ok(Source::Emote),
+            "splatmoji" => Ok(Source::Splatmoji),
+            _ => Err(format!("unknown picker '{}', expected rofimoji, emote or splatmoji", s)),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Rofimoji => "rofimoji",
+            Source::Emote => "Emote",
+            Source::Splatmoji => "splatmoji",
+        })
+    }
+}
+
+/// Kind of a file of another picker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Data,
+    History,
+}
+
+impl Source {
+    /// Recognize a data or history file by its name
+    fn classify(self, file: &Path) -> Option<FileKind> {
+        let name = file.file_name()?.to_string_lossy().to_lowercase();
+        let extension = file.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match self {
+            Source::Rofimoji if name == "recent" => Some(FileKind::History),
+            Source::Rofimoji if extension == "csv" || extension == "txt" => Some(FileKind::Data),
+            Source::Emote if extension == "json" => Some(FileKind::Data),
+            Source::Emote => Some(FileKind::History),
+            Source::Splatmoji if name == "history" => Some(FileKind::History),
+            Source::Splatmoji if extension == "tsv" => Some(FileKind::Data),
+            _ => None,
+        }
+    }
+}
+
+/// Keywords and history read from another picker
+#[derive(Debug, Default, PartialEq)]
+pub struct Imported {
+    /// Keywords of each emoticon, in the order they were read
+    pub keywords: Vec<(String, Vec<String>)>,
+    /// Recently used emoticons, most recent first
+    pub recent: Vec<String>,
+    /// Files that were read
+    pub files: Vec<PathBuf>,
+}
+
+impl Imported {
+    /// Add keywords, merging them with earlier ones of the same emoticon
+    fn add_keywords(&mut self, index: &mut HashMap<String, usize>, emoticon: &str, terms: Vec<String>) {
+        let emoticon = normalize(emoticon);
+        let position = *index.entry(emoticon.clone()).or_insert_with(|| {
+            self.keywords.push((emoticon, Vec::new()));
+            self.keywords.len() - 1
+        });
+        let known = &mut self.keywords[position].1;
+        for term in terms {
+            if !known.iter().any(|k| k.eq_ignore_ascii_case(&term)) {
+                known.push(term);
+            }
+        }
+    }
+
+    fn add_recent(&mut self, recent: Vec<String>) {
+        for emoticon in recent {
+            let emoticon = normalize(&emoticon);
+            if !self.recent.contains(&emoticon) {
+                self.recent.push(emoticon);
+            }
+        }
+    }
+}
+
+/// Read the data and history files of a picker from a file or directory
+pub fn read(source: Source, path: &Path) -> Result<Imported, String> {
+    let files: Vec<(PathBuf, FileKind)> = if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect();
+        files.sort();
+        files
+            .into_iter()
+            .filter_map(|file| source.classify(&file).map(|kind| (file, kind)))
+            .collect()
+    } else if path.is_file() {
+        let kind = source
+            .classify(path)
+            .ok_or_else(|| format!("{}: not a {} data or history file", path.display(), source))?;
+        vec![(path.to_path_buf(), kind)]
+    } else {
+        return Err(format!("{}: no such file or directory", path.display()));
+    };
+    if files.is_empty() {
+        return Err(format!("{}: no {} data or history files found", path.display(), source));
+    }
+
+    let mut imported = Imported::default();
+    let mut index = HashMap::new();
+    for (file, kind) in files {
+        let content = fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        match kind {
+            FileKind::Data => {
+                let entries = match source {
+                    Source::Rofimoji => parse_rofimoji_data(&content),
+                    Source::Emote => parse_emote_data(&content).map_err(|e| format!("{}: {}", file.display(), e))?,
+                    Source::Splatmoji => parse_splatmoji_data(&content),
+                };
+                for (emoticon, terms) in entries {
+                    imported.add_keywords(&mut index, &emoticon, terms);
+                }
+            }
+            FileKind::History => imported.add_recent(match source {
+                Source::Rofimoji => parse_lines(&content),
+                Source::Emote => parse_emote_history(&content),
+                Source::Splatmoji => parse_splatmoji_history(&content),
+            }),
+        }
+        imported.files.push(file);
+    }
+    Ok(imported)
+}
+
+/// Fully-qualified form of an emoji; other emoticons stay as they are
+fn normalize(emoticon: &str) -> String {
+    emoticons::fully_qualified(emoticon).unwrap_or(emoticon).to_string()
+}
+
+/// Split a comma separated keyword list
+fn split_terms(terms: &str) -> impl Iterator<Item = String> + '_ {
+    terms.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string)
+}
+
+/// Non-empty lines, e.g. of rofimoji's `recent` file
+fn parse_lines(content: &str) -> Vec<String> {
+    content.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect()
+}
+
+/// Parse rofimoji data lines like "😀 grinning face <small>(face, grin)</small>"
+fn parse_rofimoji_data(content: &str) -> Vec<(String, Vec<String>)> {
+    let mut entries = Vec::new();
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let (emoticon, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let (description, keywords) = match rest.split_once("<small>") {
+            Some((description, small)) => {
+                let keywords = small.trim_end().trim_end_matches("</small>").trim();
+                let keywords = keywords.strip_prefix('(').and_then(|k| k.strip_suffix(')')).unwrap_or(keywords);
+                (description.trim(), keywords)
+            }
+            None => (rest.trim(), ""),
+        };
+        let mut terms: Vec<String> = Vec::new();
+        if !description.is_empty() {
+            terms.push(description.to_string());
+        }
+        terms.extend(split_terms(keywords));
+        entries.push((emoticon.to_string(), terms));
+    }
+    entries
+}
+
+/// Parse emojibase JSON as used by Emote, e.g.
+/// `[{"emoji": "😀", "label": "grinning face", "tags": ["face", "grin"]}]`
+fn parse_emote_data(content: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let items = value.as_array().ok_or("expected a list of emoji")?;
+
+    let mut entries = Vec::new();
+    for item in items {
+        let text = |keys: &[&str]| keys.iter().find_map(|k| item.get(*k).and_then(|v| v.as_str()));
+        let Some(emoji) = text(&["emoji", "unicode"]) else {
+            continue;
+        };
+        let mut terms: Vec<String> = text(&["label", "annotation", "name"]).map(str::to_string).into_iter().collect();
+        for key in ["tags", "keywords"] {
+            if let Some(tags) = item.get(key).and_then(|v| v.as_array()) {
+                terms.extend(tags.iter().filter_map(|t| t.as_str()).map(str::to_string));
+            }
+        }
+        entries.push((emoji.to_string(), terms));
+    }
+    Ok(entries)
+}
+
+/// Parse Emote's `recent-emojis` setting, e.g. `recent-emojis=['😀', '👍']`
+fn parse_emote_history(content: &str) -> Vec<String> {
+    let value = match content.lines().find_map(|l| l.trim().strip_prefix("recent-emojis")) {
+        Some(rest) => rest.trim_start().trim_start_matches('='),
+        None => content,
+    };
+    let value = value.trim();
+    let value = value.strip_prefix("@as").unwrap_or(value).trim_start();
+    if !value.starts_with('[') {
+        return Vec::new();
+    }
+    parse_gvariant_strings(value)
+}
+
+/// Strings of a GVariant string array like `['a', "b", 'it\'s']`
+fn parse_gvariant_strings(value: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\'' && c != '"' {
+            continue;
+        }
+        let quote = c;
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => string.extend(chars.next()),
+                c if c == quote => break,
+                c => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
+
+/// Parse splatmoji data lines like "😀<TAB>grinning face, face, grin"
+fn parse_splatmoji_data(content: &str) -> Vec<(String, Vec<String>)> {
+    content
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(|line| {
+            let (emoticon, keywords) = line.split_once('\t').unwrap_or((line, ""));
+            (emoticon.trim().to_string(), split_terms(keywords).collect())
+        })
+        .collect()
+}
+
+/// Parse splatmoji's history, most used first when it has use counts
+fn parse_splatmoji_history(content: &str) -> Vec<String> {
+    let mut entries: Vec<(u64, String)> = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let mut count = 0;
+        let mut emoticon = line.trim();
+        for field in line.split('\t') {
+            match field.trim().parse::<u64>() {
+                Ok(n) => count = n,
+                Err(_) => emoticon = field.trim(),
+            }
+        }
+        entries.push((count, emoticon.to_string()));
+    }
+    // The sort is stable, so without counts the file order is kept
+    entries.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+    entries.into_iter().map(|(_, emoticon)| emoticon).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source() {
+        assert_eq!("rofimoji".parse(), Ok(Source::Rofimoji));
+        assert_eq!("Emote".parse(), Ok(Source::Emote));
+        assert!("gnome-characters".parse::<Source>().is_err());
+        assert_eq!(Source::Splatmoji.classify(Path::new("/data/emoji.tsv")), Some(FileKind::Data));
+        assert_eq!(Source::Rofimoji.classify(Path::new("/share/rofimoji/recent")), Some(FileKind::History));
+        assert_eq!(Source::Rofimoji.classify(Path::new("/share/rofimoji/README.md")), None);
+    }
+
+    #[test]
+    fn test_rofimoji() {
+        let entries = parse_rofimoji_data(
+            "😀 grinning face <small>(face, grin)</small>\n❤ red heart\n(ツ) shrug guy\n",
+        );
+        assert_eq!(entries[0], ("😀".to_string(), vec!["grinning face".to_string(), "face".into(), "grin".into()]));
+        assert_eq!(entries[1].1, vec!["red heart"]);
+        assert_eq!(entries[2].0, "(ツ)");
+        assert_eq!(parse_lines("🚀\n\n👍\n"), vec!["🚀", "👍"]);
+    }
+
+    #[test]
+    fn test_emote() {
+        let entries = parse_emote_data(
+            r#"[{"emoji": "😀", "label": "grinning face", "tags": ["face", "grin"]}, {"unicode": "🚀", "annotation": "rocket"}, {"label": "no emoji"}]"#,
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].1, vec!["grinning face", "face", "grin"]);
+        assert_eq!(entries[1], ("🚀".to_string(), vec!["rocket".to_string()]));
+        assert!(parse_emote_data("{}").is_err());
+
+        let dump = "[/]\nrecent-emojis=['🚀', '👍']\nskintone=0\n";
+        assert_eq!(parse_emote_history(dump), vec!["🚀", "👍"]);
+        assert_eq!(parse_emote_history("@as ['🎉', \"it's\"]"), vec!["🎉", "it's"]);
+        assert_eq!(parse_emote_history("@as []"), Vec::<String>::new());
+        assert_eq!(parse_gvariant_strings(r"['it\'s']"), vec!["it's"]);
+    }
+
+    #[test]
+    fn test_splatmoji() {
+        let entries = parse_splatmoji_data("😀\tgrinning face, happy\n¯\\_(ツ)_/¯\tshrug\n");
+        assert_eq!(entries[0].1, vec!["grinning face", "happy"]);
+        assert_eq!(entries[1].0, r"¯\_(ツ)_/¯");
+        assert_eq!(parse_splatmoji_history("3\t🚀\n10\t👍\n1\t🎉\n"), vec!["👍", "🚀", "🎉"]);
+        assert_eq!(parse_splatmoji_history("🚀\n👍\n"), vec!["🚀", "👍"]);
+    }
+
+    #[test]
+    fn test_read_directory() {
+        let dir = std::env::temp_dir().join(format!("smile_import_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.csv"), "❤ red heart <small>(love)</small>\n").unwrap();
+        fs::write(dir.join("b.csv"), "❤️ heavy black heart <small>(Love)</small>\n").unwrap();
+        fs::write(dir.join("recent"), "❤\n🚀\n").unwrap();
+        fs::write(dir.join("notes.md"), "ignored").unwrap();
+
+        let imported = read(Source::Rofimoji, &dir).unwrap();
+        assert_eq!(imported.files.len(), 3);
+        // Both spellings of the heart are merged into the fully-qualified one
+        assert_eq!(imported.keywords, vec![("❤️".to_string(), vec!["red heart".to_string(), "love".into(), "heavy black heart".into()])]);
+        assert_eq!(imported.recent, vec!["❤️", "🚀"]);
+
+        assert!(read(Source::Splatmoji, &dir).is_err());
+        assert!(read(Source::Rofimoji, &dir.join("missing")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
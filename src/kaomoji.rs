@@ -0,0 +1,178 @@
+//! Kaomoji parts for the kaomoji maker
+//!
+//! A kaomoji is put together from the outside in: arms, a face outline,
+//! cheeks, eyes and a mouth in the middle, e.g. `ヽ(｀Д´)ﾉ` has the arms
+//! ヽ ﾉ, the outline ( ), the eyes ｀ ´ and the mouth Д.
+
+/// A part of a kaomoji, written on both sides of the ones inside it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Part {
+    pub name: &'static str,
+    pub left: &'static str,
+    pub right: &'static str,
+}
+
+const fn part(name: &'static str, left: &'static str, right: &'static str) -> Part {
+    Part { name, left, right }
+}
+
+/// Arms, outside the face
+pub const ARMS: &[Part] = &[
+    part("none", "", ""),
+    part("cheering", "ヽ", "ﾉ"),
+    part("raised", "\\", "/"),
+    part("flexing", "ᕦ", "ᕤ"),
+    part("running", "ᕕ", "ᕗ"),
+    part("waving", "", "ﾉ"),
+    part("begging", "ლ", "ლ"),
+    part("hugging", "⊂", "⊃"),
+    part("dancing", "♪~", "~♪"),
+    part("sparkles", "✧", "✧"),
+    part("flipping", "", "╯︵ ┻━┻"),
+    part("pointing", "", "☞"),
+    part("shrugging", "¯\\_", "_/¯"),
+];
+
+/// Face outlines
+pub const OUTLINES: &[Part] = &[
+    part("round", "(", ")"),
+    part("wide", "（", "）"),
+    part("square", "[", "]"),
+    part("bear", "ʕ", "ʔ"),
+    part("fancy", "༼", "༽"),
+    part("angle", "<", ">"),
+    part("none", "", ""),
+];
+
+/// Cheeks and blushes, inside the outline
+pub const CHEEKS: &[Part] = &[
+    part("none", "", ""),
+    part("blush", "*", "*"),
+    part("flowers", "✿", "✿"),
+    part("dots", "｡", "｡"),
+    part("stars", "☆", "☆"),
+    part("sweat", "", ";"),
+    part("anger", "#", ""),
+    part("shy", "⁄ ⁄", "⁄ ⁄"),
+];
+
+/// Eyes, on both sides of the mouth
+pub const EYES: &[Part] = &[
+    part("angry", "｀", "´"),
+    part("happy", "^", "^"),
+    part("round", "◕", "◕"),
+    part("dots", "•", "•"),
+    part("small", "･", "･"),
+    part("squeezed", "≧", "≦"),
+    part("crying", "T", "T"),
+    part("tears", "╥", "╥"),
+    part("wide", "⊙", "⊙"),
+    part("disapproving", "ಠ", "ಠ"),
+    part("sideways", "¬", "¬"),
+    part("closed", "－", "－"),
+    part("sleepy", "￣", "￣"),
+    part("winking", "^", "~"),
+    part("dizzy", "@", "@"),
+    part("hearts", "♥", "♥"),
+    part("stars", "☆", "☆"),
+    part("degrees", "°", "°"),
+];
+
+/// Mouths, in the middle; only `left` is used
+pub const MOUTHS: &[Part] = &[
+    part("shouting", "Д", ""),
+    part("smile", "‿", ""),
+    part("cat", "ω", ""),
+    part("open smile", "▽", ""),
+    part("grin", "∀", ""),
+    part("flat", "_", ""),
+    part("bear", "ᴥ", ""),
+    part("furious", "益", ""),
+    part("happy", "ヮ", ""),
+    part("shocked", "□", ""),
+    part("small", "o", ""),
+    part("wavy", "﹏", ""),
+    part("kiss", "ε", ""),
+    part("content", "︶", ""),
+    part("frown", "︿", ""),
+    part("none", "", ""),
+];
+
+/// The part categories of the maker, from the outside in
+pub const CATEGORIES: [(&str, &[Part]); 5] = [
+    ("Arms", ARMS),
+    ("Face", OUTLINES),
+    ("Cheeks", CHEEKS),
+    ("Eyes", EYES),
+    ("Mouth", MOUTHS),
+];
+
+/// Put a kaomoji together from one part of each category
+pub fn compose(arms: &Part, outline: &Part, cheeks: &Part, eyes: &Part, mouth: &Part) -> String {
+    [
+        arms.left, outline.left, cheeks.left, eyes.left, mouth.left, eyes.right, cheeks.right, outline.right,
+        arms.right,
+    ]
+    .concat()
+}
+
+/// Label of a part in the maker, e.g. "cheering  ヽ ﾉ"
+pub fn label(part: &Part) -> String {
+    let sample = match (part.left, part.right) {
+        ("", "") => String::new(),
+        (left, "") => left.to_string(),
+        ("", right) => format!("… {}", right),
+        (left, right) => format!("{} … {}", left, right),
+    };
+    format!("{}  {}", part.name, sample).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(parts: &[Part], name: &str) -> Part {
+        *parts.iter().find(|p| p.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_compose() {
+        let kaomoji = compose(
+            &find(ARMS, "cheering"),
+            &find(OUTLINES, "round"),
+            &find(CHEEKS, "none"),
+            &find(EYES, "angry"),
+            &find(MOUTHS, "shouting"),
+        );
+        assert_eq!(kaomoji, "ヽ(｀Д´)ﾉ");
+
+        let kaomoji = compose(
+            &find(ARMS, "flipping"),
+            &find(OUTLINES, "round"),
+            &find(CHEEKS, "none"),
+            &find(EYES, "angry"),
+            &find(MOUTHS, "furious"),
+        );
+        assert_eq!(kaomoji, "(｀益´)╯︵ ┻━┻");
+    }
+
+    #[test]
+    fn test_parts() {
+        for (category, parts) in CATEGORIES {
+            assert!(!parts.is_empty(), "{}", category);
+            let mut names: Vec<&str> = parts.iter().map(|p| p.name).collect();
+            names.sort();
+            names.dedup();
+            assert_eq!(names.len(), parts.len(), "duplicate part names in {}", category);
+        }
+        assert!(MOUTHS.iter().all(|m| m.right.is_empty()));
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(label(&find(ARMS, "cheering")), "cheering  ヽ … ﾉ");
+        assert_eq!(label(&find(ARMS, "none")), "none");
+        assert_eq!(label(&find(MOUTHS, "cat")), "cat  ω");
+        assert_eq!(label(&find(ARMS, "waving")), "waving  … ﾉ");
+    }
+}
@@ -0,0 +1,216 @@
+//! LaTeX commands like `\alpha`, `\rightarrow` and `\le` in the search
+//!
+//! A search starting with a backslash is read as LaTeX: commands are looked
+//! up exactly, `^` and `_` become superscript and subscript characters and
+//! braces group, so `\alpha^2` is α² and `\alpha_{i+1}` is αᵢ₊₁. Only commands
+//! that stand for a single symbol are known; `\frac` and friends are not.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Commands and their symbols, as in unicode-math
+const COMMANDS: &[(&str, &str)] = &[
+    // Greek letters
+    ("alpha", "α"), ("beta", "β"), ("gamma", "γ"), ("delta", "δ"), ("epsilon", "ϵ"), ("varepsilon", "ε"),
+    ("zeta", "ζ"), ("eta", "η"), ("theta", "θ"), ("vartheta", "ϑ"), ("iota", "ι"), ("kappa", "κ"),
+    ("lambda", "λ"), ("mu", "μ"), ("nu", "ν"), ("xi", "ξ"), ("pi", "π"), ("varpi", "ϖ"), ("rho", "ρ"),
+    ("varrho", "ϱ"), ("sigma", "σ"), ("varsigma", "ς"), ("tau", "τ"), ("upsilon", "υ"), ("phi", "ϕ"),
+    ("varphi", "φ"), ("chi", "χ"), ("psi", "ψ"), ("omega", "ω"),
+    ("Gamma", "Γ"), ("Delta", "Δ"), ("Theta", "Θ"), ("Lambda", "Λ"), ("Xi", "Ξ"), ("Pi", "Π"),
+    ("Sigma", "Σ"), ("Upsilon", "Υ"), ("Phi", "Φ"), ("Psi", "Ψ"), ("Omega", "Ω"),
+    // Relations
+    ("le", "≤"), ("leq", "≤"), ("ge", "≥"), ("geq", "≥"), ("ne", "≠"), ("neq", "≠"), ("approx", "≈"),
+    ("equiv", "≡"), ("sim", "∼"), ("simeq", "≃"), ("cong", "≅"), ("propto", "∝"), ("ll", "≪"),
+    ("gg", "≫"), ("prec", "≺"), ("succ", "≻"), ("doteq", "≐"), ("subset", "⊂"), ("supset", "⊃"),
+    ("subseteq", "⊆"), ("supseteq", "⊇"), ("in", "∈"), ("notin", "∉"), ("ni", "∋"), ("perp", "⊥"),
+    ("parallel", "∥"), ("mid", "∣"), ("vdash", "⊢"), ("models", "⊨"),
+    // Arrows
+    ("rightarrow", "→"), ("to", "→"), ("leftarrow", "←"), ("gets", "←"), ("leftrightarrow", "↔"),
+    ("uparrow", "↑"), ("downarrow", "↓"), ("updownarrow", "↕"), ("Rightarrow", "⇒"),
+    ("Leftarrow", "⇐"), ("Leftrightarrow", "⇔"), ("Uparrow", "⇑"), ("Downarrow", "⇓"),
+    ("implies", "⟹"), ("iff", "⟺"), ("longrightarrow", "⟶"), ("longleftarrow", "⟵"),
+    ("mapsto", "↦"), ("longmapsto", "⟼"), ("hookrightarrow", "↪"), ("hookleftarrow", "↩"),
+    ("nearrow", "↗"), ("searrow", "↘"), ("swarrow", "↙"), ("nwarrow", "↖"),
+    ("rightleftharpoons", "⇌"),
+    // Operators
+    ("sum", "∑"), ("prod", "∏"), ("coprod", "∐"), ("int", "∫"), ("iint", "∬"), ("iiint", "∭"),
+    ("oint", "∮"), ("partial", "∂"), ("nabla", "∇"), ("infty", "∞"), ("pm", "±"), ("mp", "∓"),
+    ("times", "×"), ("div", "÷"), ("cdot", "⋅"), ("ast", "∗"), ("star", "⋆"), ("circ", "∘"),
+    ("bullet", "∙"), ("oplus", "⊕"), ("ominus", "⊖"), ("otimes", "⊗"), ("odot", "⊙"), ("cap", "∩"),
+    ("cup", "∪"), ("setminus", "∖"), ("wedge", "∧"), ("land", "∧"), ("vee", "∨"), ("lor", "∨"),
+    ("neg", "¬"), ("lnot", "¬"), ("forall", "∀"), ("exists", "∃"), ("nexists", "∄"),
+    ("emptyset", "∅"), ("varnothing", "∅"), ("sqrt", "√"), ("surd", "√"), ("therefore", "∴"),
+    ("because", "∵"), ("top", "⊤"), ("bot", "⊥"), ("dagger", "†"), ("ddagger", "‡"),
+    // Dots and delimiters
+    ("ldots", "…"), ("dots", "…"), ("cdots", "⋯"), ("vdots", "⋮"), ("ddots", "⋱"), ("langle", "⟨"),
+    ("rangle", "⟩"), ("lceil", "⌈"), ("rceil", "⌉"), ("lfloor", "⌊"), ("rfloor", "⌋"),
+    // Letter-like symbols
+    ("hbar", "ℏ"), ("ell", "ℓ"), ("Re", "ℜ"), ("Im", "ℑ"), ("aleph", "ℵ"), ("wp", "℘"), ("mho", "℧"),
+    ("prime", "′"), ("degree", "°"), ("angle", "∠"), ("triangle", "△"),
+    // Miscellaneous
+    ("checkmark", "✓"), ("spadesuit", "♠"), ("heartsuit", "♡"), ("diamondsuit", "♢"),
+    ("clubsuit", "♣"), ("flat", "♭"), ("natural", "♮"), ("sharp", "♯"), ("copyright", "©"),
+    ("S", "§"), ("P", "¶"), ("pounds", "£"), ("euro", "€"), ("Box", "□"), ("Diamond", "◇"),
+];
+
+/// Symbol of a command without its backslash, e.g. "alpha"
+pub fn lookup(command: &str) -> Option<&'static str> {
+    COMMANDS.iter().find(|(name, _)| *name == command).map(|(_, symbol)| *symbol)
+}
+
+/// The input after the backslash if a search is LaTeX, e.g. "alpha^2"
+pub fn parse_query(query: &str) -> Option<&str> {
+    query.trim().strip_prefix('\\')
+}
+
+/// Whether an emoticon is the symbol of a command, e.g. ↔️ for `\leftrightarrow`
+///
+/// Commands are case-sensitive like in `lookup()`, ↔️ isn't `\Leftrightarrow`.
+pub fn matches(emoticon: &str, command: &str) -> bool {
+    let emoticon = emoticon.trim_end_matches('\u{FE0F}');
+    COMMANDS
+        .iter()
+        .any(|(name, symbol)| *symbol == emoticon && *name == command)
+}
+
+/// Write LaTeX input as plain text, e.g. "α²" for `\alpha^2`
+///
+/// Returns None for unknown commands, unbalanced braces and characters
+/// without a superscript or subscript form.
+pub fn convert(input: &str) -> Option<String> {
+    convert_group(&mut input.trim().chars().peekable(), false)
+}
+
+/// Convert up to the closing brace of a group, or to the end
+fn convert_group(chars: &mut Peekable<Chars>, in_braces: bool) -> Option<String> {
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '}' if in_braces => return Some(text),
+            '}' => return None,
+            '{' => text.push_str(&convert_group(chars, true)?),
+            '\\' => text.push_str(&convert_command(chars)?),
+            '^' => text.push_str(&convert_script(chars, superscript)?),
+            '_' => text.push_str(&convert_script(chars, subscript)?),
+            c => text.push(c),
+        }
+    }
+    // An open brace was not closed
+    (!in_braces).then_some(text)
+}
+
+/// Convert the command after a backslash
+fn convert_command(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+        name.push(c);
+        chars.next();
+    }
+    if !name.is_empty() {
+        return lookup(&name).map(str::to_string);
+    }
+    // Escaped characters like \{ and \_
+    match chars.next()? {
+        '|' => Some("‖".to_string()),
+        c @ ('{' | '}' | '_' | '^' | '%' | '$' | '&' | '#' | '\\' | ' ') => Some(c.to_string()),
+        _ => None,
+    }
+}
+
+/// Convert the argument of `^` or `_`: a group, a command or a character
+fn convert_script(chars: &mut Peekable<Chars>, script: fn(char) -> Option<char>) -> Option<String> {
+    let argument = match chars.next()? {
+        '{' => convert_group(chars, true)?,
+        '\\' => convert_command(chars)?,
+        c => c.to_string(),
+    };
+    argument.chars().map(script).collect()
+}
+
+fn superscript(c: char) -> Option<char> {
+    let script = match c {
+        '0' => '⁰', '1' => '¹', '2' => '²', '3' => '³', '4' => '⁴',
+        '5' => '⁵', '6' => '⁶', '7' => '⁷', '8' => '⁸', '9' => '⁹',
+        '+' => '⁺', '-' => '⁻', '=' => '⁼', '(' => '⁽', ')' => '⁾', ' ' => ' ',
+        'a' => 'ᵃ', 'b' => 'ᵇ', 'c' => 'ᶜ', 'd' => 'ᵈ', 'e' => 'ᵉ', 'f' => 'ᶠ', 'g' => 'ᵍ',
+        'h' => 'ʰ', 'i' => 'ⁱ', 'j' => 'ʲ', 'k' => 'ᵏ', 'l' => 'ˡ', 'm' => 'ᵐ', 'n' => 'ⁿ',
+        'o' => 'ᵒ', 'p' => 'ᵖ', 'r' => 'ʳ', 's' => 'ˢ', 't' => 'ᵗ', 'u' => 'ᵘ', 'v' => 'ᵛ',
+        'w' => 'ʷ', 'x' => 'ˣ', 'y' => 'ʸ', 'z' => 'ᶻ',
+        'A' => 'ᴬ', 'B' => 'ᴮ', 'D' => 'ᴰ', 'E' => 'ᴱ', 'G' => 'ᴳ', 'H' => 'ᴴ', 'I' => 'ᴵ',
+        'J' => 'ᴶ', 'K' => 'ᴷ', 'L' => 'ᴸ', 'M' => 'ᴹ', 'N' => 'ᴺ', 'O' => 'ᴼ', 'P' => 'ᴾ',
+        'R' => 'ᴿ', 'T' => 'ᵀ', 'U' => 'ᵁ', 'V' => 'ⱽ', 'W' => 'ᵂ',
+        'α' => 'ᵅ', 'β' => 'ᵝ', 'γ' => 'ᵞ', 'δ' => 'ᵟ', 'ε' => 'ᵋ', 'θ' => 'ᶿ', 'φ' => 'ᵠ',
+        'χ' => 'ᵡ',
+        // Already raised, e.g. ^\prime
+        '′' | '°' | '*' | '∗' => c,
+        _ => return None,
+    };
+    Some(script)
+}
+
+fn subscript(c: char) -> Option<char> {
+    let script = match c {
+        '0' => '₀', '1' => '₁', '2' => '₂', '3' => '₃', '4' => '₄',
+        '5' => '₅', '6' => '₆', '7' => '₇', '8' => '₈', '9' => '₉',
+        '+' => '₊', '-' => '₋', '=' => '₌', '(' => '₍', ')' => '₎', ' ' => ' ',
+        'a' => 'ₐ', 'e' => 'ₑ', 'h' => 'ₕ', 'i' => 'ᵢ', 'j' => 'ⱼ', 'k' => 'ₖ', 'l' => 'ₗ',
+        'm' => 'ₘ', 'n' => 'ₙ', 'o' => 'ₒ', 'p' => 'ₚ', 'r' => 'ᵣ', 's' => 'ₛ', 't' => 'ₜ',
+        'u' => 'ᵤ', 'v' => 'ᵥ', 'x' => 'ₓ',
+        'β' => 'ᵦ', 'γ' => 'ᵧ', 'ρ' => 'ᵨ', 'φ' => 'ᵩ', 'χ' => 'ᵪ',
+        _ => return None,
+    };
+    Some(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup("alpha"), Some("α"));
+        assert_eq!(lookup("rightarrow"), Some("→"));
+        assert_eq!(lookup("sum"), Some("∑"));
+        assert_eq!(lookup("le"), Some("≤"));
+        // Commands are case-sensitive
+        assert_eq!(lookup("Delta"), Some("Δ"));
+        assert_eq!(lookup("delta"), Some("δ"));
+        assert_eq!(lookup("alph"), None);
+
+        let mut names: Vec<&str> = COMMANDS.iter().map(|(name, _)| *name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), COMMANDS.len(), "duplicate commands");
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(convert("\\alpha").as_deref(), Some("α"));
+        assert_eq!(convert("\\alpha^2").as_deref(), Some("α²"));
+        assert_eq!(convert("x_{i+1}").as_deref(), Some("xᵢ₊₁"));
+        assert_eq!(convert("\\sum_{n=0}^\\infty").as_deref(), None);
+        assert_eq!(convert("\\sum_{n=0}^{10} a_n").as_deref(), Some("∑ₙ₌₀¹⁰ aₙ"));
+        assert_eq!(convert("\\alpha \\to \\beta").as_deref(), Some("α → β"));
+        assert_eq!(convert("\\Delta{}E \\le \\hbar").as_deref(), Some("ΔE ≤ ℏ"));
+        assert_eq!(convert("e^{i\\pi} = -1").as_deref(), None);
+        assert_eq!(convert("f^\\prime").as_deref(), Some("f′"));
+        assert_eq!(convert("\\{\\_\\}").as_deref(), Some("{_}"));
+
+        // Unknown commands, unbalanced braces and missing script forms
+        assert_eq!(convert("\\frac{1}{2}"), None);
+        assert_eq!(convert("x^{2"), None);
+        assert_eq!(convert("x}"), None);
+        assert_eq!(convert("x^q"), None);
+        assert_eq!(convert("x^"), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("↔️", "leftrightarrow"));
+        assert!(matches("♠️", "spadesuit"));
+        assert!(!matches("➡️", "rightarrow"));
+        assert!(!matches("↔️", "Leftrightarrow"));
+        assert_eq!(parse_query(" \\alpha^2 "), Some("alpha^2"));
+        assert_eq!(parse_query("alpha"), None);
+    }
+}
@@ -4,6 +4,7 @@
 //! - Shows all available emoticons in a grid
 //! - Search field to filter emoticons
 //! - Shows last 10 used emoticons
+//! - Composer for ZWJ sequences (professions, families, gendered variants)
 //! - Clicks paste the emoticon and reopen the window
 //! - Enter key pastes emoticon and closes the application
 //! - Shift+Enter pastes emoticon and reopens the window
@@ -19,14 +20,64 @@ use gtk::{
 use std::cell::RefCell;
 use std::rc::Rc;
 
+mod annotations;
+
+mod catalog;
+
 mod emoticons;
-use emoticons::get_emoticons;
+use emoticons::{get_emoticons, SkinTone};
+
+mod cli;
+
+mod composer;
+
+mod custom;
+
+mod data_cache;
+
+mod data_files;
+
+mod favorites;
+use favorites::Favorite;
+
+mod filters;
+
+mod packs;
+
+mod providers;
+use providers::{Cancellation, Provider};
+
+mod render;
+
+mod gitmoji;
+use gitmoji::Gitmoji;
+
+mod glyphs;
+
+mod images;
+use images::ImageEmoji;
+
+mod import;
+
+mod kaomoji;
+
+mod latex;
+
+mod shortcodes;
+
+mod symbols;
+use symbols::CharInfo;
+
+mod textstyle;
+use textstyle::Style;
 
 mod uinput;
 use uinput::UinputKeyboard;
 
 mod settings;
-use settings::{Config, WindowState};
+use settings::{Config, GitmojiMode, LayoutCategory, PasteShortcut, UnsupportedGlyphs, WindowState};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 #[cfg(test)]
 mod tests;
@@ -37,12 +88,46 @@ mod tests;
 struct EmoticonPicker {
     window: ApplicationWindow,
     search_entry: Entry,
+    stack: gtk::Stack,
     emoticons_box: Box,
+    categories: Rc<Vec<LayoutCategory>>,
+    unsupported: Rc<HashSet<String>>,
     history: Rc<RefCell<Vec<String>>>,
+    favorites: Rc<RefCell<Vec<Favorite>>>,
+    /// Images from ~/.smile/images/
+    images: Rc<Vec<ImageEmoji>>,
+    /// Loaded images by path; None if an image can't be loaded
+    textures: Rc<RefCell<HashMap<PathBuf, Option<gdk::Texture>>>>,
+    /// Built-in and script providers, searched along with the categories
+    providers: Rc<Vec<&'static dyn Provider>>,
+    /// Cancels the provider searches of the previous search text
+    provider_searches: Rc<RefCell<Cancellation>>,
+    /// Emoticons typed since the picker was opened, offered when saving a favorite
+    typed: Rc<RefCell<String>>,
     config: Rc<RefCell<Config>>,
     first_button: Rc<RefCell<Option<Button>>>,
+    symbols: SymbolBrowser,
+    /// Previews of the search text in each text style
+    styles: FlowBox,
 }
 
+/// Widgets of the Unicode symbol browser
+#[derive(Clone)]
+struct SymbolBrowser {
+    /// Browse by block or by general category
+    mode: gtk::DropDown,
+    /// The block or category that is shown
+    group: gtk::DropDown,
+    flowbox: FlowBox,
+    status: Label,
+}
+
+/// Most characters shown at once in the symbol browser
+const MAX_SYMBOL_BUTTONS: usize = 1500;
+
+/// Category offered for kaomoji saved from the kaomoji maker
+const KAOMOJI_MAKER_CATEGORY: &str = "Kaomoji: Made by me";
+
 
 impl EmoticonPicker {
     /// Create a new emoticon picker window
@@ -50,6 +135,26 @@ impl EmoticonPicker {
         // Setup configuration
         let config = Config::new().expect("Failed to initialize configuration");
 
+        // Prefer the emoji data of `smile update-data` over the built-in tables
+        data_cache::use_cache(config.config_dir());
+
+        // Load user-defined emoticons and packs before the emoticon table is built
+        emoticons::use_custom_emoticons(config.config_dir());
+
+        // Script providers live as long as the picker, like the built-in ones
+        let scripts = config.settings().providers.clone().leak();
+        let mut search_providers = providers::built_in().to_vec();
+        search_providers.extend(scripts.iter().map(|p| p as &dyn Provider));
+
+        let (favorites, favorite_errors) = favorites::load_favorites(&config.config_dir().join("favorites.toml"));
+        for error in &favorite_errors {
+            eprintln!("{}", error);
+        }
+        let (image_emoji, image_errors) = images::load_images(&config.config_dir().join("images"));
+        for error in &image_errors {
+            eprintln!("{}", error);
+        }
+
         // Create window with default or saved dimensions
         let window = ApplicationWindow::builder()
             .application(app)
@@ -75,6 +180,23 @@ impl EmoticonPicker {
         // Load history
         let history = config.load_recent();
 
+        // Arrange the categories as configured in the settings
+        let mut categories = config.settings().categories(get_emoticons());
+
+        // Find the emoticons the installed fonts can't render
+        let unsupported_glyphs = config.settings().unsupported_glyphs;
+        let unsupported = if unsupported_glyphs == UnsupportedGlyphs::Show {
+            HashSet::new()
+        } else {
+            Self::find_unsupported_glyphs(&config, &window.pango_context(), &categories)
+        };
+        if unsupported_glyphs == UnsupportedGlyphs::Hide {
+            for category in &mut categories {
+                category.emoticons.retain(|e| !unsupported.contains(*e));
+            }
+            categories.retain(|c| !c.emoticons.is_empty());
+        }
+
         // Main container
         let main_box = Box::new(Orientation::Vertical, 10);
         main_box.set_margin_start(10);
@@ -92,6 +214,13 @@ impl EmoticonPicker {
         search_entry.set_hexpand(true);
         search_box.append(&search_entry);
 
+        // Switch between the emoticon grid and the composer
+        let stack = gtk::Stack::new();
+        stack.set_vexpand(true);
+        let stack_switcher = gtk::StackSwitcher::new();
+        stack_switcher.set_stack(Some(&stack));
+        search_box.append(&stack_switcher);
+
         main_box.append(&search_box);
 
         // Scrolled window for emoticons
@@ -103,27 +232,86 @@ impl EmoticonPicker {
         let emoticons_box = Box::new(Orientation::Vertical, 10);
         scrolled.set_child(Some(&emoticons_box));
 
-        main_box.append(&scrolled);
+        stack.add_titled(&scrolled, Some("emoticons"), "Emoticons");
+        main_box.append(&stack);
 
         window.set_child(Some(&main_box));
 
+        let symbols = SymbolBrowser {
+            mode: gtk::DropDown::from_strings(&["By block", "By category"]),
+            group: gtk::DropDown::from_strings(&[]),
+            flowbox: Self::create_flowbox(),
+            status: Label::new(None),
+        };
+
         let picker = EmoticonPicker {
             window: window.clone(),
             search_entry: search_entry.clone(),
+            stack: stack.clone(),
             emoticons_box: emoticons_box.clone(),
+            categories: Rc::new(categories),
+            unsupported: Rc::new(unsupported),
             history: Rc::new(RefCell::new(history)),
+            favorites: Rc::new(RefCell::new(favorites)),
+            images: Rc::new(image_emoji),
+            textures: Rc::new(RefCell::new(HashMap::new())),
+            providers: Rc::new(search_providers),
+            provider_searches: Rc::new(RefCell::new(Cancellation::default())),
+            typed: Rc::new(RefCell::new(String::new())),
             config: Rc::new(RefCell::new(config)),
             first_button: Rc::new(RefCell::new(None)),
+            symbols,
+            styles: Self::create_flowbox(),
         };
 
         // Build the emoticon display
         picker.build_emoticons_display("");
 
+        // Build the ZWJ sequence composer
+        let composer_page = picker.build_composer();
+        stack.add_titled(&composer_page, Some("composer"), "Composer");
+
+        // Put kaomoji together from arms, eyes, mouths, ...
+        let kaomoji_page = picker.build_kaomoji_maker();
+        stack.add_titled(&kaomoji_page, Some("kaomoji"), "Kaomoji Maker");
+
+        // Browse Unicode symbols by block or category
+        let symbols_page = picker.build_symbols_page();
+        stack.add_titled(&symbols_page, Some("symbols"), "Symbols");
+
+        // Write the search text in bold, italic, upside-down, ...
+        let styles_page = picker.build_styles_page();
+        stack.add_titled(&styles_page, Some("styles"), "Text Styles");
+
+        // The search applies to the emoticons, the symbols or the text styles, whichever is shown
+        let picker_clone = picker.clone();
+        stack.connect_visible_child_name_notify(move |stack| {
+            let filter_text = picker_clone.search_entry.text().to_string();
+            match stack.visible_child_name().as_deref() {
+                Some("symbols") => picker_clone.build_symbols_display(&filter_text),
+                Some("styles") => picker_clone.build_styles_display(&filter_text),
+                Some("emoticons") => picker_clone.build_emoticons_display(&filter_text),
+                _ => {}
+            }
+        });
+
         // Connect search entry key press event for Down arrow navigation
         let key_controller = EventControllerKey::new();
         let picker_clone = picker.clone();
         key_controller.connect_key_pressed(move |_, key, _, _| {
-            if key == gdk::Key::Down {
+            let page = picker_clone.stack.visible_child_name();
+            let flowbox = match page.as_deref() {
+                Some("symbols") => Some(&picker_clone.symbols.flowbox),
+                Some("styles") => Some(&picker_clone.styles),
+                _ => None,
+            };
+            if let (gdk::Key::Down, Some(flowbox)) = (key, flowbox) {
+                // Focus the first symbol or text style when Down is pressed
+                if let Some(button) = flowbox.child_at_index(0).and_then(|c| c.child()) {
+                    button.grab_focus();
+                    return glib::Propagation::Stop;
+                }
+            } else if key == gdk::Key::Down {
                 // Focus the first button when Down is pressed
                 if let Some(ref button) = *picker_clone.first_button.borrow() {
                     button.grab_focus();
@@ -138,7 +326,14 @@ impl EmoticonPicker {
         let picker_clone = picker.clone();
         search_entry.connect_changed(move |entry| {
             let filter_text = entry.text().to_string();
-            picker_clone.build_emoticons_display(&filter_text);
+            match picker_clone.stack.visible_child_name().as_deref() {
+                Some("symbols") => picker_clone.build_symbols_display(&filter_text),
+                Some("styles") => picker_clone.build_styles_display(&filter_text),
+                _ => {
+                    picker_clone.stack.set_visible_child_name("emoticons");
+                    picker_clone.build_emoticons_display(&filter_text);
+                }
+            }
         });
 
         // Connect key press event for Escape on window
@@ -152,7 +347,9 @@ impl EmoticonPicker {
             }
             // Auto-focus search field when typing (printable characters)
             // Check if a printable character is typed and search is not already focused
-            if !picker_clone.search_entry.has_focus() {
+            // The composer page keeps its own keyboard handling
+            let searchable = matches!(picker_clone.stack.visible_child_name().as_deref(), Some("emoticons" | "symbols" | "styles"));
+            if searchable && !picker_clone.search_entry.has_focus() {
                 if let Some(ch) = key.to_unicode() {
                     // Get current text and cursor position
                     let current_text = picker_clone.search_entry.text();
@@ -252,20 +449,12 @@ impl EmoticonPicker {
         let history = self.history.borrow();
         if !history.is_empty() && filter_text.is_empty() {
             let history_frame = Frame::new(Some("Recently Used"));
-            let history_flowbox = FlowBox::new();
-            history_flowbox.set_selection_mode(gtk::SelectionMode::None);
-            history_flowbox.set_max_children_per_line(30);
-            history_flowbox.set_column_spacing(5);
-            history_flowbox.set_row_spacing(5);
-            history_flowbox.set_margin_start(10);
-            history_flowbox.set_margin_end(10);
-            history_flowbox.set_margin_top(10);
-            history_flowbox.set_margin_bottom(10);
-            history_flowbox.set_homogeneous(false);
+            let history_flowbox = Self::create_flowbox();
 
             let mut is_first = true;
             for emoticon in history.iter() {
-                let button = self.create_emoticon_button(emoticon);
+                // Recents hold the exact variant that was chosen
+                let button = self.create_emoticon_button(emoticon, false, None);
 
                 // Store the first button for focus navigation
                 if is_first {
@@ -281,22 +470,77 @@ impl EmoticonPicker {
         }
         drop(history);
 
-        // Show categorized emoticons
-        for (category, emoticons) in get_emoticons().iter() {
+        // An exact shortcode hit like ":tada:" comes first
+        let shortcode_set = self.config.borrow().settings().shortcodes;
+        if let Some(emoji) = shortcodes::emoji_for(shortcode_set, filter_text.trim()) {
+            let code = shortcodes::parse_query(filter_text.trim()).unwrap_or_default().to_lowercase();
+            let shortcode_frame = Frame::new(Some(&format!(":{}:", code)));
+            let shortcode_flowbox = Self::create_flowbox();
+
+            let button = self.create_emoticon_button(emoji, true, None);
+            *self.first_button.borrow_mut() = Some(button.clone());
+            shortcode_flowbox.append(&button);
+
+            shortcode_frame.set_child(Some(&shortcode_flowbox));
+            self.emoticons_box.append(&shortcode_frame);
+        }
+
+        // LaTeX input like "\alpha^2" comes first as the text it stands for
+        if let Some(text) = latex::parse_query(filter_text).and_then(|_| latex::convert(filter_text)) {
+            let latex_frame = Frame::new(Some(filter_text.trim()));
+            let latex_flowbox = Self::create_flowbox();
+
+            let button = self.create_emoticon_button(&text, false, None);
+            *self.first_button.borrow_mut() = Some(button.clone());
+            latex_flowbox.append(&button);
+
+            latex_frame.set_child(Some(&latex_flowbox));
+            self.emoticons_box.append(&latex_frame);
+        }
+
+        // Saved favorites, with a button to save another one when not searching
+        let favorites = self.favorites.borrow();
+        let matching: Vec<&Favorite> = favorites
+            .iter()
+            .filter(|f| filter_text.is_empty() || f.matches(&filter_lower))
+            .collect();
+        if !matching.is_empty() || filter_text.is_empty() {
+            let favorites_frame = Frame::new(Some("Favorites"));
+            let favorites_flowbox = Self::create_flowbox();
+
+            for favorite in matching {
+                let button = self.create_emoticon_button(&favorite.text, false, None);
+                if self.first_button.borrow().is_none() {
+                    *self.first_button.borrow_mut() = Some(button.clone());
+                }
+                favorites_flowbox.append(&button);
+            }
+            if filter_text.is_empty() {
+                favorites_flowbox.append(&self.create_save_favorite_button());
+            }
+
+            favorites_frame.set_child(Some(&favorites_flowbox));
+            self.emoticons_box.append(&favorites_frame);
+        }
+        drop(favorites);
+
+        // Show categorized emoticons in the configured layout
+        for category in self.categories.iter() {
             // Filter emoticons
-            let filtered_emoticons: Vec<&String> = if !filter_text.is_empty() {
+            let filtered_emoticons: Vec<&str> = if !filter_text.is_empty() {
                 let config = self.config.borrow();
-                emoticons
+                // Match category name, display name or keywords
+                let category_matches = config.matches_category_keywords(&category.name, &filter_lower)
+                    || config.matches_category_keywords(&category.label, &filter_lower);
+                category
+                    .emoticons
                     .iter()
-                    .filter(|e| {
-                        // Match emoticon keywords or the emoticon itself
-                        config.matches_emoticon_keywords(e, &filter_lower) ||
-                        // Match category name or keywords
-                        config.matches_category_keywords(category, &filter_lower)
-                    })
+                    .copied()
+                    // Match emoticon keywords or the emoticon itself
+                    .filter(|e| category_matches || config.matches_emoticon_keywords(e, &filter_lower))
                     .collect()
             } else {
-                emoticons.iter().collect()
+                category.emoticons.clone()
             };
 
             if filtered_emoticons.is_empty() {
@@ -304,22 +548,16 @@ impl EmoticonPicker {
             }
 
             // Category frame
-            let frame = Frame::new(Some(category));
-            let flowbox = FlowBox::new();
-            flowbox.set_selection_mode(gtk::SelectionMode::None);
-            flowbox.set_max_children_per_line(30);
-            flowbox.set_column_spacing(5);
-            flowbox.set_row_spacing(5);
-            flowbox.set_margin_start(10);
-            flowbox.set_margin_end(10);
-            flowbox.set_margin_top(10);
-            flowbox.set_margin_bottom(10);
-            flowbox.set_homogeneous(false);
+            let frame = Frame::new(Some(&category.label));
+            let flowbox = Self::create_flowbox();
 
             // Add emoticons to flowbox
             let mut is_first = self.first_button.borrow().is_none();
+            let is_gitmoji = category.name == gitmoji::GITMOJI_CATEGORY;
             for emoticon in filtered_emoticons {
-                let button = self.create_emoticon_button(emoticon);
+                // Gitmoji are typed without a skin tone, like in commit messages
+                let gitmoji = if is_gitmoji { gitmoji::lookup(emoticon) } else { None };
+                let button = self.create_emoticon_button(emoticon, gitmoji.is_none(), gitmoji);
 
                 // Store the first button for focus navigation if not already set
                 if is_first {
@@ -334,6 +572,44 @@ impl EmoticonPicker {
             self.emoticons_box.append(&frame);
         }
 
+        // Images can't be typed, they are put on the clipboard
+        let matching_images: Vec<&ImageEmoji> = {
+            let config = self.config.borrow();
+            self.images
+                .iter()
+                .filter(|image| {
+                    filter_text.is_empty()
+                        || image.matches(&filter_lower)
+                        || config.matches_category_keywords(images::IMAGES_CATEGORY, &filter_lower)
+                })
+                .collect()
+        };
+        if !matching_images.is_empty() {
+            let images_frame = Frame::new(Some(images::IMAGES_CATEGORY));
+            let images_flowbox = Self::create_flowbox();
+
+            for image in matching_images {
+                let button = self.create_image_button(image);
+                if self.first_button.borrow().is_none() {
+                    *self.first_button.borrow_mut() = Some(button.clone());
+                }
+                images_flowbox.append(&button);
+            }
+
+            images_frame.set_child(Some(&images_flowbox));
+            self.emoticons_box.append(&images_frame);
+        }
+
+        // Providers that search by themselves add their results when they are done;
+        // searches still running for the previous text are stopped
+        let cancellation = Cancellation::default();
+        self.provider_searches.replace(cancellation.clone()).cancel();
+        if !filter_text.trim().is_empty() {
+            for &provider in self.providers.iter() {
+                self.show_provider_results(provider, filter_text, &cancellation);
+            }
+        }
+
         self.window.present();
 
         // Focus the first button if available (and search is empty)
@@ -348,8 +624,79 @@ impl EmoticonPicker {
         }
     }
 
+    /// Search a provider in the background and show its items in a section
+    ///
+    /// The section is appended right away, so the order of the providers is
+    /// kept; it stays hidden until items are found. The search starts after
+    /// the provider's debounce time and is cancelled, along with its results,
+    /// when the display is rebuilt for another search meanwhile.
+    fn show_provider_results(&self, provider: &'static dyn Provider, query: &str, cancellation: &Cancellation) {
+        let frame = Frame::new(Some(provider.name()));
+        frame.set_visible(false);
+        self.emoticons_box.append(&frame);
+
+        let query = query.to_string();
+        let cancellation = cancellation.clone();
+        let picker = self.clone();
+        glib::spawn_future_local(async move {
+            let debounce = provider.debounce();
+            if !debounce.is_zero() {
+                glib::timeout_future(debounce).await;
+            }
+            if cancellation.is_cancelled() {
+                return;
+            }
+
+            let search = cancellation.clone();
+            let items = match gtk::gio::spawn_blocking(move || provider.search(&query, &search)).await {
+                Ok(Ok(items)) => items,
+                Ok(Err(e)) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+                Err(_) => return,
+            };
+            if items.is_empty() || cancellation.is_cancelled() || frame.parent().is_none() {
+                return;
+            }
+
+            let flowbox = Self::create_flowbox();
+            for item in &items {
+                let button = picker.create_emoticon_button(item.text(), false, None);
+                if let Some(name) = item.name() {
+                    button.set_tooltip_text(Some(name));
+                }
+                if picker.first_button.borrow().is_none() {
+                    *picker.first_button.borrow_mut() = Some(button.clone());
+                }
+                flowbox.append(&button);
+            }
+            frame.set_child(Some(&flowbox));
+            frame.set_visible(true);
+        });
+    }
+
+    /// Create a flowbox for a frame of emoticon buttons
+    fn create_flowbox() -> FlowBox {
+        let flowbox = FlowBox::new();
+        flowbox.set_selection_mode(gtk::SelectionMode::None);
+        flowbox.set_max_children_per_line(30);
+        flowbox.set_column_spacing(5);
+        flowbox.set_row_spacing(5);
+        flowbox.set_margin_start(10);
+        flowbox.set_margin_end(10);
+        flowbox.set_margin_top(10);
+        flowbox.set_margin_bottom(10);
+        flowbox.set_homogeneous(false);
+        flowbox
+    }
+
     /// Create a button for an emoticon
-    fn create_emoticon_button(&self, emoticon: &str) -> Button {
+    ///
+    /// With `apply_skin_tone` the default skin tone from the settings is used
+    /// when the emoticon is typed. Entries of the Gitmoji category pass their
+    /// `gitmoji` to show its meaning and type it as configured.
+    fn create_emoticon_button(&self, emoticon: &str, apply_skin_tone: bool, gitmoji: Option<&'static Gitmoji>) -> Button {
         let button = Button::with_label(emoticon);
         button.set_size_request(50, 40);
         button.set_hexpand(false);
@@ -357,27 +704,129 @@ impl EmoticonPicker {
         button.set_can_focus(true);
         button.set_focus_on_click(true);
 
-        let emoticon = emoticon.to_string();
+        // Show the Unicode name, the Emoji version and the shortcodes as tooltip
+        let mut tooltip: Vec<String> = Vec::new();
+        let mut chars = emoticon.chars();
+        if let Some(entry) = emoticons::lookup(emoticon) {
+            tooltip.push(entry.name.to_string());
+            tooltip.push(format!("Emoji {}", entry.version));
+            if let Some(gitmoji) = gitmoji {
+                tooltip.push(gitmoji::tooltip(gitmoji));
+            }
+        } else if let Some(favorite) = self.favorites.borrow().iter().find(|f| f.text == emoticon) {
+            tooltip.push(favorite.name.clone());
+        } else if let (Some(c), None) = (chars.next(), chars.next()) {
+            // Characters from the symbol browser in the recents
+            if let Some(info) = symbols::lookup(c) {
+                tooltip.push(info.name.to_string());
+            }
+        }
+        let config = self.config.borrow();
+        let settings = config.settings();
+        let codes: Vec<String> = shortcodes::shortcodes_of(settings.shortcodes, emoticon)
+            .iter()
+            .map(|code| format!(":{}:", code))
+            .collect();
+        if !codes.is_empty() {
+            tooltip.push(codes.join(" "));
+        }
+
+        // Grey out emoticons the installed fonts can't render
+        if self.unsupported.contains(emoticon) {
+            button.add_css_class("dim-label");
+            tooltip.push("Not supported by the installed fonts".to_string());
+        }
+
+        // Flag emoji above the version cap, e.g. in the recents
+        if let Some(max) = settings.max_emoji_version.filter(|_| settings.exceeds_max_emoji_version(emoticon)) {
+            button.add_css_class("warning");
+            tooltip.push(format!("Newer than Emoji {} (max_emoji_version)", max));
+        }
+
+        if !tooltip.is_empty() {
+            button.set_tooltip_text(Some(&tooltip.join("\n")));
+        }
+        drop(config);
+
+        let emoticon = if apply_skin_tone {
+            self.apply_default_skin_tone(emoticon)
+        } else {
+            emoticon.to_string()
+        };
+
+        // Right-click or long-press opens the menu: skin tone variants and image export
+        let right_click = gtk::GestureClick::new();
+        right_click.set_button(gdk::BUTTON_SECONDARY);
+        let emoticon_for_menu = emoticon.clone();
+        let picker = self.clone();
+        right_click.connect_pressed(move |gesture, _, _, _| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            if let Some(widget) = gesture.widget() {
+                picker.show_emoticon_menu(&widget, &emoticon_for_menu);
+            }
+        });
+        button.add_controller(right_click);
+
+        let long_press = gtk::GestureLongPress::new();
+        let emoticon_for_menu = emoticon.clone();
+        let picker = self.clone();
+        long_press.connect_pressed(move |gesture, _, _| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            if let Some(widget) = gesture.widget() {
+                picker.show_emoticon_menu(&widget, &emoticon_for_menu);
+            }
+        });
+        button.add_controller(long_press);
+
+        // Ctrl+click types the shortcode instead of the emoticon
+        let shortcode_click = gtk::GestureClick::new();
+        shortcode_click.set_button(gdk::BUTTON_PRIMARY);
+        shortcode_click.set_propagation_phase(gtk::PropagationPhase::Capture);
+        let emoticon_for_shortcode = emoticon.clone();
+        let picker = self.clone();
+        shortcode_click.connect_released(move |gesture, _, _, _| {
+            if gesture.current_event_state().contains(gdk::ModifierType::CONTROL_MASK) {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                picker.on_shortcode_clicked(&emoticon_for_shortcode, false);
+            }
+        });
+        button.add_controller(shortcode_click);
 
         // Clone for the click handler
         let emoticon_for_click = emoticon.clone();
         let picker = self.clone();
         button.connect_clicked(move |_| {
-            picker.on_emoticon_clicked(&emoticon_for_click, false); // Default: close app after paste
+            // Default: close app after paste
+            match gitmoji {
+                Some(gitmoji) => picker.on_gitmoji_clicked(gitmoji, false),
+                None => picker.on_emoticon_clicked(&emoticon_for_click, false),
+            }
         });
 
         // Add key press handler for Enter, Shift+Enter, and arrow keys
         let key_controller = EventControllerKey::new();
         let emoticon_for_key = emoticon.clone();
         let picker_for_key = self.clone();
-        key_controller.connect_key_pressed(move |_, key, _, modifiers| {
-            if key == gdk::Key::Return || key == gdk::Key::KP_Enter {
-                if modifiers.contains(gdk::ModifierType::SHIFT_MASK) {
-                    // Shift+Enter: paste and reopen
-                    picker_for_key.on_emoticon_clicked(&emoticon_for_key, true);
-                } else {
-                    // Enter: paste and close
-                    picker_for_key.on_emoticon_clicked(&emoticon_for_key, false);
+        key_controller.connect_key_pressed(move |controller, key, _, modifiers| {
+            if (key == gdk::Key::Return || key == gdk::Key::KP_Enter)
+                && modifiers.contains(gdk::ModifierType::CONTROL_MASK)
+            {
+                // Ctrl+Enter: type the shortcode, with Shift reopen afterwards
+                let reopen = modifiers.contains(gdk::ModifierType::SHIFT_MASK);
+                picker_for_key.on_shortcode_clicked(&emoticon_for_key, reopen);
+                return glib::Propagation::Stop;
+            } else if key == gdk::Key::Return || key == gdk::Key::KP_Enter {
+                // Enter: paste and close, Shift+Enter: paste and reopen
+                let reopen = modifiers.contains(gdk::ModifierType::SHIFT_MASK);
+                match gitmoji {
+                    Some(gitmoji) => picker_for_key.on_gitmoji_clicked(gitmoji, reopen),
+                    None => picker_for_key.on_emoticon_clicked(&emoticon_for_key, reopen),
+                }
+                return glib::Propagation::Stop;
+            } else if key == gdk::Key::Menu {
+                // Menu key: show the skin tone variants and the image export
+                if let Some(widget) = controller.widget() {
+                    picker_for_key.show_emoticon_menu(&widget, &emoticon_for_key);
                 }
                 return glib::Propagation::Stop;
             } else if key == gdk::Key::Up || key == gdk::Key::Down ||
@@ -392,6 +841,831 @@ impl EmoticonPicker {
         button
     }
 
+    /// Check which emoticons the fonts can't render, using the cache when the fonts are unchanged
+    fn find_unsupported_glyphs(
+        config: &Config,
+        context: &gtk::pango::Context,
+        categories: &[LayoutCategory],
+    ) -> HashSet<String> {
+        let emoticons = || categories.iter().flat_map(|c| c.emoticons.iter().copied());
+        let fingerprint = glyphs::fingerprint(context, emoticons());
+
+        if let Some(unsupported) = config.load_glyph_cache(&fingerprint) {
+            return unsupported;
+        }
+
+        let unsupported = glyphs::unsupported(context, emoticons());
+        config.save_glyph_cache(&fingerprint, &unsupported).ok();
+        unsupported
+    }
+
+    /// Build the composer page for ZWJ sequences
+    ///
+    /// The user picks a role, a person variant and a skin tone; the preview
+    /// shows the resulting sequence. Only RGI sequences can be produced.
+    fn build_composer(&self) -> Box {
+        let roles = composer::roles();
+
+        let composer_box = Box::new(Orientation::Vertical, 10);
+        composer_box.set_margin_start(10);
+        composer_box.set_margin_end(10);
+        composer_box.set_margin_top(10);
+        composer_box.set_margin_bottom(10);
+
+        let role_names: Vec<&str> = roles.iter().map(|r| r.name.as_str()).collect();
+        let role_dropdown = gtk::DropDown::from_strings(&role_names);
+        role_dropdown.set_enable_search(true);
+        role_dropdown.set_hexpand(true);
+
+        let variant_dropdown = gtk::DropDown::from_strings(&[]);
+        let tone_labels: Vec<&str> = SkinTone::ALL.iter().map(|t| t.label()).collect();
+        let tone_dropdown = gtk::DropDown::from_strings(&tone_labels);
+        let default_tone = self.config.borrow().settings().default_skin_tone;
+        if let Some(index) = SkinTone::ALL.iter().position(|&t| t == default_tone) {
+            tone_dropdown.set_selected(index as u32);
+        }
+
+        // Selection grid
+        let grid = gtk::Grid::new();
+        grid.set_row_spacing(5);
+        grid.set_column_spacing(10);
+        for (row, (title, dropdown)) in [
+            ("Role:", &role_dropdown),
+            ("Person:", &variant_dropdown),
+            ("Skin tone:", &tone_dropdown),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let label = Label::new(Some(title));
+            label.set_xalign(0.0);
+            grid.attach(&label, 0, row as i32, 1, 1);
+            grid.attach(dropdown, 1, row as i32, 1, 1);
+        }
+        composer_box.append(&grid);
+
+        // Preview of the resulting sequence
+        let preview_label = Label::new(None);
+        preview_label.set_margin_top(20);
+        let name_label = Label::new(None);
+        let parts_label = Label::new(None);
+        parts_label.set_wrap(true);
+        let codepoints_label = Label::new(None);
+        codepoints_label.set_selectable(true);
+        composer_box.append(&preview_label);
+        composer_box.append(&name_label);
+        composer_box.append(&parts_label);
+        composer_box.append(&codepoints_label);
+
+        let insert_button = Button::with_label("Insert");
+        insert_button.set_halign(gtk::Align::Center);
+        composer_box.append(&insert_button);
+
+        let result: Rc<RefCell<Option<&'static str>>> = Rc::new(RefCell::new(None));
+
+        // Refresh the preview from the current selection
+        let update_preview = {
+            let role_dropdown = role_dropdown.clone();
+            let variant_dropdown = variant_dropdown.clone();
+            let tone_dropdown = tone_dropdown.clone();
+            let insert_button = insert_button.clone();
+            let result = result.clone();
+            Rc::new(move || {
+                let variant = roles
+                    .get(role_dropdown.selected() as usize)
+                    .and_then(|role| role.variants.get(variant_dropdown.selected() as usize))
+                    .map(|(_, emoji)| *emoji);
+                let tone = SkinTone::ALL
+                    .get(tone_dropdown.selected() as usize)
+                    .copied()
+                    .unwrap_or_default();
+
+                let composed = variant.and_then(|variant| {
+                    tone_dropdown.set_sensitive(emoticons::supports_skin_tone(variant));
+                    composer::compose(variant, tone)
+                });
+
+                match composed {
+                    Some(emoji) => {
+                        preview_label.set_markup(&format!(
+                            "<span size=\"400%\">{}</span>",
+                            glib::markup_escape_text(emoji)
+                        ));
+                        name_label.set_text(emoticons::lookup(emoji).map(|e| e.name).unwrap_or_default());
+                        parts_label.set_text(&composer::describe_parts(emoji));
+                        codepoints_label.set_text(&emoticons::format_codepoints(emoji));
+                    }
+                    None => {
+                        preview_label.set_text("");
+                        name_label.set_text("Not available with this skin tone");
+                        parts_label.set_text("");
+                        codepoints_label.set_text("");
+                    }
+                }
+                insert_button.set_sensitive(composed.is_some());
+                *result.borrow_mut() = composed;
+            })
+        };
+
+        // Changing the role offers its person variants
+        let variant_dropdown_for_role = variant_dropdown.clone();
+        let update_for_role = update_preview.clone();
+        let fill_variants = move |role_dropdown: &gtk::DropDown| {
+            if let Some(role) = roles.get(role_dropdown.selected() as usize) {
+                let labels: Vec<&str> = role.variants.iter().map(|(label, _)| label.as_str()).collect();
+                variant_dropdown_for_role.set_model(Some(&gtk::StringList::new(&labels)));
+                variant_dropdown_for_role.set_selected(0);
+            }
+            update_for_role();
+        };
+        fill_variants(&role_dropdown);
+        role_dropdown.connect_selected_notify(fill_variants);
+
+        let update_for_variant = update_preview.clone();
+        variant_dropdown.connect_selected_notify(move |_| update_for_variant());
+        let update_for_tone = update_preview.clone();
+        tone_dropdown.connect_selected_notify(move |_| update_for_tone());
+
+        // Insert the composed sequence like any other emoji
+        let picker = self.clone();
+        insert_button.connect_clicked(move |_| {
+            if let Some(emoji) = *result.borrow() {
+                picker.on_emoticon_clicked(emoji, false);
+            }
+        });
+
+        composer_box
+    }
+
+    /// Build the kaomoji maker page
+    ///
+    /// One part of each category is picked, from the arms to the mouth. The
+    /// result is typed like a classic emoticon, or saved to custom.toml with
+    /// keywords so it can be searched after a restart.
+    fn build_kaomoji_maker(&self) -> Box {
+        let maker_box = Box::new(Orientation::Vertical, 10);
+        maker_box.set_margin_start(10);
+        maker_box.set_margin_end(10);
+        maker_box.set_margin_top(10);
+        maker_box.set_margin_bottom(10);
+
+        // One dropdown per part category
+        let grid = gtk::Grid::new();
+        grid.set_row_spacing(5);
+        grid.set_column_spacing(10);
+        let dropdowns: Vec<gtk::DropDown> = kaomoji::CATEGORIES
+            .iter()
+            .enumerate()
+            .map(|(row, (title, parts))| {
+                let labels: Vec<String> = parts.iter().map(kaomoji::label).collect();
+                let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+                let dropdown = gtk::DropDown::from_strings(&labels);
+                dropdown.set_hexpand(true);
+
+                let label = Label::new(Some(&format!("{}:", title)));
+                label.set_xalign(0.0);
+                grid.attach(&label, 0, row as i32, 1, 1);
+                grid.attach(&dropdown, 1, row as i32, 1, 1);
+                dropdown
+            })
+            .collect();
+        // Start out with "ヽ(｀Д´)ﾉ" rather than a bare face
+        dropdowns[0].set_selected(1);
+        maker_box.append(&grid);
+
+        let preview_label = Label::new(None);
+        preview_label.set_margin_top(20);
+        preview_label.set_selectable(true);
+        maker_box.append(&preview_label);
+
+        let insert_button = Button::with_label("Insert");
+        insert_button.set_halign(gtk::Align::Center);
+        maker_box.append(&insert_button);
+
+        // Saving as a custom emoticon
+        let save_grid = gtk::Grid::new();
+        save_grid.set_row_spacing(5);
+        save_grid.set_column_spacing(10);
+        save_grid.set_margin_top(20);
+        let keywords_entry = Entry::new();
+        keywords_entry.set_placeholder_text(Some("angry, table flip"));
+        keywords_entry.set_hexpand(true);
+        let category_entry = Entry::new();
+        category_entry.set_text(KAOMOJI_MAKER_CATEGORY);
+        for (row, (title, entry)) in [("Keywords:", &keywords_entry), ("Category:", &category_entry)]
+            .into_iter()
+            .enumerate()
+        {
+            let label = Label::new(Some(title));
+            label.set_xalign(0.0);
+            save_grid.attach(&label, 0, row as i32, 1, 1);
+            save_grid.attach(entry, 1, row as i32, 1, 1);
+        }
+        let save_button = Button::with_label("Save to custom.toml");
+        save_button.set_halign(gtk::Align::End);
+        save_grid.attach(&save_button, 1, 2, 1, 1);
+        maker_box.append(&save_grid);
+
+        let status_label = Label::new(None);
+        status_label.set_wrap(true);
+        maker_box.append(&status_label);
+
+        let result = Rc::new(RefCell::new(String::new()));
+
+        // Refresh the preview from the current selection
+        let update_preview = {
+            let dropdowns = dropdowns.clone();
+            let result = result.clone();
+            let status_label = status_label.clone();
+            move || {
+                let part = |index: usize| {
+                    let parts = kaomoji::CATEGORIES[index].1;
+                    parts.get(dropdowns[index].selected() as usize).unwrap_or(&parts[0])
+                };
+                let composed = kaomoji::compose(part(0), part(1), part(2), part(3), part(4));
+                preview_label.set_markup(&format!("<span size=\"400%\">{}</span>", glib::markup_escape_text(&composed)));
+                status_label.set_text("");
+                *result.borrow_mut() = composed;
+            }
+        };
+        update_preview();
+        let update_preview = Rc::new(update_preview);
+        for dropdown in &dropdowns {
+            let update_preview = update_preview.clone();
+            dropdown.connect_selected_notify(move |_| update_preview());
+        }
+
+        // Type the kaomoji the same way as the classic emoticons
+        let picker = self.clone();
+        let result_for_insert = result.clone();
+        insert_button.connect_clicked(move |_| {
+            let text = result_for_insert.borrow().clone();
+            picker.on_emoticon_clicked(&text, false);
+        });
+
+        let picker = self.clone();
+        save_button.connect_clicked(move |_| {
+            let emoticon = custom::CustomEmoticon::new(
+                result.borrow().clone(),
+                category_entry.text().trim().to_string(),
+                keywords_entry
+                    .text()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|k| !k.is_empty())
+                    .map(String::from)
+                    .collect(),
+            );
+            if let Some(message) = custom::validate(&emoticon, emoticons::custom_emoticons()) {
+                status_label.set_text(&message);
+                return;
+            }
+            let path = picker.config.borrow().config_dir().join("custom.toml");
+            match custom::append_custom(&path, &emoticon) {
+                Ok(()) => status_label.set_text(&format!(
+                    "Saved {} to {}; it shows up in '{}' after a restart",
+                    emoticon.text,
+                    path.display(),
+                    emoticon.category
+                )),
+                Err(e) => status_label.set_text(&format!("Failed to save {}: {}", path.display(), e)),
+            }
+        });
+
+        maker_box
+    }
+
+    /// Apply the default skin tone from the settings to a modifier-capable emoticon
+    fn apply_default_skin_tone(&self, emoticon: &str) -> String {
+        let config = self.config.borrow();
+        let tone = config.settings().default_skin_tone;
+        if tone != SkinTone::None && emoticons::skin_tone_of(emoticon) == Some(SkinTone::None) {
+            // Some toned variants are newer than the base emoji, e.g. 🤝🏻
+            if let Some(variant) = emoticons::with_skin_tone(emoticon, tone)
+                .filter(|v| !config.settings().exceeds_max_emoji_version(v))
+            {
+                return variant.to_string();
+            }
+        }
+        emoticon.to_string()
+    }
+
+    /// Build the symbol browser page
+    ///
+    /// Shows the characters of one Unicode block or general category; with
+    /// a search text it shows the characters whose names match instead.
+    fn build_symbols_page(&self) -> Box {
+        let page = Box::new(Orientation::Vertical, 10);
+
+        let controls = Box::new(Orientation::Horizontal, 5);
+        controls.append(&Label::new(Some("Browse:")));
+        controls.append(&self.symbols.mode);
+        self.symbols.group.set_enable_search(true);
+        self.symbols.group.set_hexpand(true);
+        controls.append(&self.symbols.group);
+        page.append(&controls);
+
+        let scrolled = ScrolledWindow::new();
+        scrolled.set_policy(PolicyType::Never, PolicyType::Automatic);
+        scrolled.set_vexpand(true);
+        scrolled.set_child(Some(&self.symbols.flowbox));
+        page.append(&scrolled);
+
+        self.symbols.status.set_xalign(0.0);
+        self.symbols.status.add_css_class("dim-label");
+        page.append(&self.symbols.status);
+
+        self.update_symbol_groups();
+
+        let picker = self.clone();
+        self.symbols.mode.connect_selected_notify(move |_| {
+            picker.update_symbol_groups();
+        });
+        let picker = self.clone();
+        self.symbols.group.connect_selected_notify(move |_| {
+            // Browsing replaces the search; clearing it rebuilds the display
+            if picker.search_entry.text().is_empty() {
+                picker.build_symbols_display("");
+            } else {
+                picker.search_entry.set_text("");
+            }
+        });
+
+        page
+    }
+
+    /// Fill the block or category list for the selected browse mode
+    fn update_symbol_groups(&self) {
+        let by_block = self.symbols.mode.selected() == 0;
+        let names: Vec<&str> = if by_block {
+            symbols::blocks().map(|b| b.name).collect()
+        } else {
+            symbols::CATEGORIES.iter().map(|(_, name)| *name).collect()
+        };
+        self.symbols.group.set_model(Some(&gtk::StringList::new(&names)));
+
+        // Start with the arrows, the most wanted block
+        let start = if by_block { names.iter().position(|n| *n == "Arrows") } else { None };
+        self.symbols.group.set_selected(start.unwrap_or(0) as u32);
+        self.build_symbols_display(&self.search_entry.text());
+    }
+
+    /// Build or rebuild the characters of the symbol browser
+    fn build_symbols_display(&self, filter_text: &str) {
+        self.symbols.flowbox.remove_all();
+
+        let selected = self.symbols.group.selected() as usize;
+        let characters: Vec<&'static CharInfo> = if !filter_text.trim().is_empty() {
+            symbols::search(filter_text, usize::MAX)
+        } else if self.symbols.mode.selected() == 0 {
+            symbols::blocks()
+                .nth(selected)
+                .map(|b| b.characters().iter().collect())
+                .unwrap_or_default()
+        } else {
+            symbols::CATEGORIES
+                .get(selected)
+                .map(|(category, _)| symbols::in_category(category))
+                .unwrap_or_default()
+        };
+
+        for info in characters.iter().take(MAX_SYMBOL_BUTTONS) {
+            self.symbols.flowbox.append(&self.create_symbol_button(info));
+        }
+
+        let status = if characters.len() > MAX_SYMBOL_BUTTONS {
+            format!("Showing {} of {} characters, search by name to narrow down", MAX_SYMBOL_BUTTONS, characters.len())
+        } else {
+            format!("{} characters", characters.len())
+        };
+        self.symbols.status.set_text(&status);
+    }
+
+    /// Create a button for a character of the symbol browser
+    fn create_symbol_button(&self, info: &'static CharInfo) -> Button {
+        let button = Button::with_label(&symbols::label(info));
+        button.set_size_request(50, 40);
+        button.set_can_focus(true);
+        button.set_focus_on_click(true);
+        button.set_tooltip_text(Some(&format!("{}\nU+{:04X}", info.name, info.ch as u32)));
+
+        let symbol = info.ch;
+        let picker = self.clone();
+        button.connect_clicked(move |_| {
+            picker.on_symbol_clicked(symbol, false);
+        });
+
+        // Enter, Shift+Enter and arrow keys work like on the emoticon buttons
+        let key_controller = EventControllerKey::new();
+        let picker = self.clone();
+        key_controller.connect_key_pressed(move |_, key, _, modifiers| {
+            if key == gdk::Key::Return || key == gdk::Key::KP_Enter {
+                picker.on_symbol_clicked(symbol, modifiers.contains(gdk::ModifierType::SHIFT_MASK));
+                return glib::Propagation::Stop;
+            } else if key == gdk::Key::Up || key == gdk::Key::Down ||
+                      key == gdk::Key::Left || key == gdk::Key::Right {
+                return picker.handle_arrow_navigation(key);
+            }
+            glib::Propagation::Proceed
+        });
+        button.add_controller(key_controller);
+
+        button
+    }
+
+    /// Build the text style page
+    ///
+    /// Previews the search text in every style; picking one types it.
+    fn build_styles_page(&self) -> Box {
+        let page = Box::new(Orientation::Vertical, 10);
+
+        self.styles.set_max_children_per_line(1);
+        let scrolled = ScrolledWindow::new();
+        scrolled.set_policy(PolicyType::Automatic, PolicyType::Automatic);
+        scrolled.set_vexpand(true);
+        scrolled.set_child(Some(&self.styles));
+        page.append(&scrolled);
+
+        self.build_styles_display(&self.search_entry.text());
+        page
+    }
+
+    /// Build or rebuild the previews of the text style page
+    fn build_styles_display(&self, text: &str) {
+        self.styles.remove_all();
+        for style in Style::ALL {
+            self.styles.append(&self.create_style_button(style, text));
+        }
+    }
+
+    /// Create a button that types a text in a style
+    ///
+    /// Without a text the button shows the name of the style and does nothing.
+    fn create_style_button(&self, style: Style, text: &str) -> Button {
+        let preview = if text.is_empty() { style.label() } else { text };
+        let styled = style.apply(preview);
+
+        let content = Box::new(Orientation::Horizontal, 10);
+        let name = Label::new(Some(style.label()));
+        name.set_width_chars(14);
+        name.set_xalign(0.0);
+        name.add_css_class("dim-label");
+        content.append(&name);
+        let label = Label::new(Some(&styled));
+        label.set_xalign(0.0);
+        label.set_selectable(false);
+        content.append(&label);
+
+        let button = Button::new();
+        button.set_child(Some(&content));
+        button.set_can_focus(true);
+        button.set_focus_on_click(true);
+        button.set_sensitive(!text.is_empty());
+        button.set_tooltip_text(Some(&format!("{}\nsmile style {}", style.label(), style.name())));
+
+        let picker = self.clone();
+        let typed = styled.clone();
+        button.connect_clicked(move |_| {
+            picker.on_style_clicked(&typed, false);
+        });
+
+        let key_controller = EventControllerKey::new();
+        let picker = self.clone();
+        key_controller.connect_key_pressed(move |_, key, _, modifiers| {
+            if key == gdk::Key::Return || key == gdk::Key::KP_Enter {
+                picker.on_style_clicked(&styled, modifiers.contains(gdk::ModifierType::SHIFT_MASK));
+                return glib::Propagation::Stop;
+            } else if key == gdk::Key::Up || key == gdk::Key::Down {
+                return picker.handle_arrow_navigation(key);
+            }
+            glib::Propagation::Proceed
+        });
+        button.add_controller(key_controller);
+
+        button
+    }
+
+    /// Create a button with the thumbnail of an image emoji
+    fn create_image_button(&self, image: &ImageEmoji) -> Button {
+        let button = Button::new();
+        button.set_size_request(50, 40);
+        button.set_can_focus(true);
+        button.set_focus_on_click(true);
+        match self.texture(image) {
+            Some(texture) => {
+                let thumbnail = gtk::Image::from_paintable(Some(&texture));
+                thumbnail.set_pixel_size(32);
+                button.set_child(Some(&thumbnail));
+            }
+            None => button.set_label(&image.name),
+        }
+
+        let mut tooltip = vec![image.name.clone()];
+        if !image.keywords.is_empty() {
+            tooltip.push(image.keywords.join(", "));
+        }
+        tooltip.push("Copied to the clipboard as PNG".to_string());
+        button.set_tooltip_text(Some(&tooltip.join("\n")));
+
+        let picker = self.clone();
+        let image_for_click = image.clone();
+        button.connect_clicked(move |_| {
+            picker.on_image_clicked(&image_for_click, false);
+        });
+
+        let key_controller = EventControllerKey::new();
+        let picker = self.clone();
+        let image = image.clone();
+        key_controller.connect_key_pressed(move |_, key, _, modifiers| {
+            if key == gdk::Key::Return || key == gdk::Key::KP_Enter {
+                picker.on_image_clicked(&image, modifiers.contains(gdk::ModifierType::SHIFT_MASK));
+                return glib::Propagation::Stop;
+            } else if key == gdk::Key::Up || key == gdk::Key::Down || key == gdk::Key::Left || key == gdk::Key::Right {
+                return picker.handle_arrow_navigation(key);
+            }
+            glib::Propagation::Proceed
+        });
+        button.add_controller(key_controller);
+
+        button
+    }
+
+    /// Load an image emoji, once
+    ///
+    /// Images that can't be loaded are reported the first time only.
+    fn texture(&self, image: &ImageEmoji) -> Option<gdk::Texture> {
+        self.textures
+            .borrow_mut()
+            .entry(image.path.clone())
+            .or_insert_with(|| match gdk::Texture::from_filename(&image.path) {
+                Ok(texture) => Some(texture),
+                Err(e) => {
+                    eprintln!("Failed to load {}: {}", image.path.display(), e);
+                    None
+                }
+            })
+            .clone()
+    }
+
+    /// Create the button that saves a sequence of emoticons as a favorite
+    fn create_save_favorite_button(&self) -> Button {
+        let button = Button::with_label("＋");
+        button.set_size_request(50, 40);
+        button.set_can_focus(true);
+        button.set_tooltip_text(Some("Save emoticons as a favorite"));
+
+        let picker = self.clone();
+        button.connect_clicked(move |button| {
+            picker.show_save_favorite_popover(button.upcast_ref());
+        });
+
+        let key_controller = EventControllerKey::new();
+        let picker = self.clone();
+        key_controller.connect_key_pressed(move |_, key, _, _| {
+            if key == gdk::Key::Up || key == gdk::Key::Down ||
+               key == gdk::Key::Left || key == gdk::Key::Right {
+                return picker.handle_arrow_navigation(key);
+            }
+            glib::Propagation::Proceed
+        });
+        button.add_controller(key_controller);
+
+        button
+    }
+
+    /// Show a popover to save a sequence of emoticons as a favorite
+    ///
+    /// The sequence starts out as the emoticons typed since the picker was
+    /// opened, so Shift+Enter a few of them and save them here.
+    fn show_save_favorite_popover(&self, parent: &gtk::Widget) {
+        let popover = gtk::Popover::new();
+        popover.set_parent(parent);
+
+        let form = gtk::Grid::new();
+        form.set_row_spacing(5);
+        form.set_column_spacing(5);
+
+        let text = Entry::new();
+        text.set_text(&self.typed.borrow());
+        text.set_placeholder_text(Some("🎉🎊🥳"));
+        let name = Entry::new();
+        name.set_placeholder_text(Some("Party"));
+        let keywords = Entry::new();
+        keywords.set_placeholder_text(Some("celebrate, congrats"));
+        for (row, (label, entry)) in [("Emoticons", &text), ("Name", &name), ("Keywords", &keywords)]
+            .into_iter()
+            .enumerate()
+        {
+            let label = Label::new(Some(label));
+            label.set_xalign(0.0);
+            form.attach(&label, 0, row as i32, 1, 1);
+            form.attach(entry, 1, row as i32, 1, 1);
+        }
+
+        let error = Label::new(None);
+        error.add_css_class("error");
+        error.set_wrap(true);
+        error.set_xalign(0.0);
+        form.attach(&error, 0, 3, 2, 1);
+
+        let save = Button::with_label("Save");
+        save.add_css_class("suggested-action");
+        save.set_halign(gtk::Align::End);
+        form.attach(&save, 1, 4, 1, 1);
+        popover.set_child(Some(&form));
+
+        // Enter in any field saves
+        for entry in [&text, &name, &keywords] {
+            let save = save.clone();
+            entry.connect_activate(move |_| save.emit_clicked());
+        }
+
+        let picker = self.clone();
+        let popover_for_save = popover.clone();
+        save.connect_clicked(move |_| {
+            let favorite = Favorite {
+                text: text.text().trim().to_string(),
+                name: name.text().trim().to_string(),
+                keywords: keywords
+                    .text()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|k| !k.is_empty())
+                    .map(String::from)
+                    .collect(),
+            };
+            if let Some(message) = favorites::validate(&favorite, &picker.favorites.borrow()) {
+                error.set_text(&message);
+                return;
+            }
+            let path = picker.config.borrow().config_dir().join("favorites.toml");
+            if let Err(e) = favorites::append_favorite(&path, &favorite) {
+                error.set_text(&format!("Failed to save {}: {}", path.display(), e));
+                return;
+            }
+            picker.favorites.borrow_mut().push(favorite);
+            picker.typed.borrow_mut().clear();
+
+            // Rebuild once the popover is gone from the button it belongs to
+            popover_for_save.popdown();
+            let picker = picker.clone();
+            glib::idle_add_local_once(move || {
+                picker.build_emoticons_display(&picker.search_entry.text());
+            });
+        });
+        // Remove the popover from its parent once it is closed
+        popover.connect_closed(|popover| {
+            let popover = popover.clone();
+            glib::idle_add_local_once(move || popover.unparent());
+        });
+
+        popover.popup();
+    }
+
+    /// Show the menu of an emoticon: its skin tone variants, if any, and the image export
+    fn show_emoticon_menu(&self, parent: &gtk::Widget, emoticon: &str) {
+        let popover = gtk::Popover::new();
+        popover.set_parent(parent);
+
+        let menu_box = Box::new(Orientation::Vertical, 10);
+        if emoticons::supports_skin_tone(emoticon) {
+            let variants_box = Box::new(Orientation::Horizontal, 5);
+            let variants: Vec<&str> = {
+                let config = self.config.borrow();
+                emoticons::skin_tone_variants(emoticon)
+                    .into_iter()
+                    .filter(|v| !config.settings().exceeds_max_emoji_version(v))
+                    .collect()
+            };
+            for variant in variants {
+                let button = Button::with_label(variant);
+                button.set_size_request(50, 40);
+                if let Some(entry) = emoticons::lookup(variant) {
+                    button.set_tooltip_text(Some(entry.name));
+                }
+
+                let picker = self.clone();
+                let popover_for_click = popover.clone();
+                button.connect_clicked(move |_| {
+                    popover_for_click.popdown();
+                    // The chosen variant is typed as is, without the default skin tone
+                    picker.on_emoticon_clicked(variant, false);
+                });
+                variants_box.append(&button);
+            }
+            menu_box.append(&variants_box);
+        }
+        menu_box.append(&self.create_image_export(emoticon, &popover));
+        popover.set_child(Some(&menu_box));
+
+        // Remove the popover from its parent once it is closed
+        popover.connect_closed(|popover| {
+            let popover = popover.clone();
+            glib::idle_add_local_once(move || popover.unparent());
+        });
+
+        popover.popup();
+    }
+
+    /// Create the image export of an emoticon's menu
+    ///
+    /// The emoticon is rendered in the emoji font of the settings at the
+    /// chosen size and saved as PNG or SVG, or copied to the clipboard.
+    fn create_image_export(&self, emoticon: &str, popover: &gtk::Popover) -> gtk::Grid {
+        let grid = gtk::Grid::new();
+        grid.set_row_spacing(5);
+        grid.set_column_spacing(10);
+
+        let title = Label::new(Some("Image"));
+        title.add_css_class("heading");
+        title.set_xalign(0.0);
+        grid.attach(&title, 0, 0, 2, 1);
+
+        let defaults = render::Options::default();
+        let size = gtk::SpinButton::with_range(16.0, f64::from(render::MAX_SIZE), 16.0);
+        size.set_value(f64::from(defaults.size));
+        let padding = gtk::SpinButton::with_range(0.0, f64::from(render::MAX_SIZE / 2), 1.0);
+        padding.set_value(f64::from(defaults.padding));
+        for (row, (title, spin)) in [("Size:", &size), ("Padding:", &padding)].into_iter().enumerate() {
+            let label = Label::new(Some(title));
+            label.set_xalign(0.0);
+            grid.attach(&label, 0, row as i32 + 1, 1, 1);
+            grid.attach(spin, 1, row as i32 + 1, 1, 1);
+        }
+        let transparent = gtk::CheckButton::with_label("Transparent background");
+        transparent.set_active(true);
+        grid.attach(&transparent, 0, 3, 2, 1);
+
+        let buttons = Box::new(Orientation::Horizontal, 5);
+        buttons.set_halign(gtk::Align::End);
+        let save = Button::with_label("Save…");
+        let copy = Button::with_label("Copy");
+        buttons.append(&save);
+        buttons.append(&copy);
+        grid.attach(&buttons, 0, 4, 2, 1);
+
+        let error = Label::new(None);
+        error.add_css_class("error");
+        error.set_wrap(true);
+        error.set_xalign(0.0);
+        grid.attach(&error, 0, 5, 2, 1);
+
+        // Options from the current input; transparent or white
+        let font = self.config.borrow().settings().emoji_font.clone();
+        let options = move || render::Options {
+            size: size.value() as u32,
+            padding: padding.value() as u32,
+            background: (!transparent.is_active()).then_some(gdk::RGBA::WHITE),
+            font: font.clone().unwrap_or_else(|| render::DEFAULT_FONT.to_string()),
+        };
+        let options = Rc::new(options);
+
+        let picker = self.clone();
+        let text = emoticon.to_string();
+        let options_for_save = options.clone();
+        let error_for_save = error.clone();
+        let popover_for_save = popover.clone();
+        save.connect_clicked(move |_| {
+            let options = options_for_save();
+            if let Err(e) = options.validate() {
+                error_for_save.set_text(&e);
+                return;
+            }
+            let name = emoticons::lookup(&text).map(|entry| entry.name);
+            let dialog = gtk::FileDialog::new();
+            dialog.set_initial_name(Some(&render::file_name(&text, name, render::Format::Png)));
+            popover_for_save.popdown();
+
+            // The file name decides between PNG and SVG
+            let text = text.clone();
+            dialog.save(Some(&picker.window), None::<&gtk::gio::Cancellable>, move |result| {
+                let Some(path) = result.ok().and_then(|file| file.path()) else {
+                    return;
+                };
+                if let Err(e) = render::render_to_file(&text, &options, &path) {
+                    eprintln!("{}", e);
+                }
+            });
+        });
+
+        let picker = self.clone();
+        let text = emoticon.to_string();
+        let popover_for_copy = popover.clone();
+        copy.connect_clicked(move |_| {
+            let texture = render::render_png(&text, &options())
+                .and_then(|png| gdk::Texture::from_bytes(&glib::Bytes::from_owned(png)).map_err(|e| e.to_string()));
+            match texture {
+                Ok(texture) => {
+                    popover_for_copy.popdown();
+                    picker.serve_clipboard(&texture, PasteShortcut::Off, false);
+                }
+                Err(e) => error.set_text(&e),
+            }
+        });
+
+        grid
+    }
+
     /// Handle arrow key navigation between emoticon buttons
     fn handle_arrow_navigation(&self, key: gdk::Key) -> glib::Propagation {
         // Get the currently focused widget
@@ -515,17 +1789,106 @@ impl EmoticonPicker {
 
     /// Handle emoticon button click
     fn on_emoticon_clicked(&self, emoticon: &str, reopen: bool) {
+        self.insert_emoticon(emoticon, emoticon, reopen);
+    }
+
+    /// Type the shortcode of an emoticon instead of the emoticon itself
+    ///
+    /// Emoticons without a shortcode in the configured set are typed as they are.
+    fn on_shortcode_clicked(&self, emoticon: &str, reopen: bool) {
+        let set = self.config.borrow().settings().shortcodes;
+        let text = shortcodes::shortcode(set, emoticon).unwrap_or_else(|| emoticon.to_string());
+        self.insert_emoticon(emoticon, &text, reopen);
+    }
+
+    /// Type a Gitmoji as its emoji or its code, as set by `gitmoji` in the settings
+    fn on_gitmoji_clicked(&self, gitmoji: &Gitmoji, reopen: bool) {
+        let text = match self.config.borrow().settings().gitmoji {
+            GitmojiMode::Code => gitmoji.code,
+            GitmojiMode::Glyph | GitmojiMode::Off => gitmoji.emoji,
+        };
+        self.insert_emoticon(gitmoji.emoji, text, reopen);
+    }
+
+    /// Handle a click on a character of the symbol browser
+    fn on_symbol_clicked(&self, symbol: char, reopen: bool) {
+        self.add_to_history(symbol.to_string());
+        self.typed.borrow_mut().push(symbol);
+        self.window.set_visible(false);
+
+        let picker = self.clone();
+        glib::timeout_add_local(std::time::Duration::from_millis(10), move || {
+            picker.paste_symbol(symbol, reopen);
+            glib::ControlFlow::Break
+        });
+    }
+
+    /// Type a styled text; it is not added to the history
+    fn on_style_clicked(&self, text: &str, reopen: bool) {
+        self.window.set_visible(false);
+
+        let text = text.to_string();
+        let picker = self.clone();
+        glib::timeout_add_local(std::time::Duration::from_millis(10), move || {
+            picker.paste_emoticon(&text, reopen);
+            glib::ControlFlow::Break
+        });
+    }
+
+    /// Put an image emoji on the clipboard and paste it with the configured shortcut
+    fn on_image_clicked(&self, image: &ImageEmoji, reopen: bool) {
+        let Some(texture) = self.texture(image) else {
+            return;
+        };
+        let shortcut = self.config.borrow().settings().image_paste;
+        self.serve_clipboard(&texture, shortcut, reopen);
+    }
+
+    /// Put an image on the clipboard, hide the window and paste with `shortcut`
+    ///
+    /// Without `reopen` the picker keeps serving the clipboard in the
+    /// background until another application takes it over.
+    fn serve_clipboard(&self, texture: &gdk::Texture, shortcut: PasteShortcut, reopen: bool) {
+        // GDK offers a texture as image/png, among other formats
+        let clipboard = self.window.clipboard();
+        clipboard.set_texture(texture);
+        self.window.set_visible(false);
+
+        let picker = self.clone();
+        glib::spawn_future_local(async move {
+            if shortcut != PasteShortcut::Off {
+                Self::modifiers_released().await;
+                Self::with_uinput(|keyboard| keyboard.paste(shortcut));
+            }
+
+            if reopen {
+                picker.reopen_window();
+            } else if !clipboard.is_local() {
+                std::process::exit(0);
+            } else {
+                clipboard.connect_changed(|clipboard| {
+                    if !clipboard.is_local() {
+                        std::process::exit(0);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Remember an emoticon in the history and type the given text for it
+    fn insert_emoticon(&self, emoticon: &str, text: &str, reopen: bool) {
         // Add to history
         self.add_to_history(emoticon.to_string());
+        self.typed.borrow_mut().push_str(emoticon);
 
         // Hide window
         self.window.set_visible(false);
 
         // Paste with minimal delay (just enough to let window hide)
-        let emoticon = emoticon.to_string();
+        let text = text.to_string();
         let picker = self.clone();
         glib::timeout_add_local(std::time::Duration::from_millis(10), move || {
-            picker.paste_emoticon(&emoticon, reopen);
+            picker.paste_emoticon(&text, reopen);
             glib::ControlFlow::Break
         });
     }
@@ -533,50 +1896,26 @@ impl EmoticonPicker {
     /// Paste the emoticon using uinput (kernel-level input injection)
     fn paste_emoticon(&self, emoticon: &str, reopen: bool) {
         let emoticon = emoticon.to_string();
+        self.paste_with(reopen, move |keyboard| keyboard.type_string(&emoticon));
+    }
+
+    /// Type a single character of the symbol browser using uinput
+    fn paste_symbol(&self, symbol: char, reopen: bool) {
+        self.paste_with(reopen, move |keyboard| keyboard.type_unicode_char(symbol));
+    }
+
+    /// Type with uinput once the modifier keys are released, then reopen or exit
+    fn paste_with(
+        &self,
+        reopen: bool,
+        type_text: impl FnOnce(&mut UinputKeyboard) -> std::io::Result<()> + 'static,
+    ) {
         let picker = self.clone();
 
         // Use glib's async to avoid blocking GTK
         glib::spawn_future_local(async move {
-            // Wait for modifier keys to be released before typing
-            // This is important for Shift+Enter to work correctly
-            let mut wait_count = 0;
-            let max_wait = 10; // 10 * 50ms = 500ms max
-
-            while wait_count < max_wait {
-                glib::timeout_future(std::time::Duration::from_millis(50)).await;
-                wait_count += 1;
-
-                // Check if modifier keys are still pressed
-                let mut has_modifiers = false;
-                if let Some(display) = gtk::gdk::Display::default() {
-                    if let Some(seat) = display.default_seat() {
-                        if let Some(device) = seat.keyboard() {
-                            let modifier_state = device.modifier_state();
-                            has_modifiers = modifier_state.contains(gdk::ModifierType::SHIFT_MASK) ||
-                                          modifier_state.contains(gdk::ModifierType::CONTROL_MASK) ||
-                                          modifier_state.contains(gdk::ModifierType::ALT_MASK);
-                        }
-                    }
-                }
-
-                if !has_modifiers {
-                    break;
-                }
-            }
-
-            // Type emoticon
-            match UinputKeyboard::new() {
-                Ok(mut keyboard) => {
-                    if let Err(e) = keyboard.type_string(&emoticon) {
-                        eprintln!("Failed to type emoticon via uinput: {}", e);
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Failed to create uinput device: {}", e);
-                    eprintln!("Note: uinput requires write access to /dev/uinput or /dev/input/uinput");
-                    eprintln!("You may need to add your user to the 'input' group or run with appropriate permissions.");
-                }
-            }
+            Self::modifiers_released().await;
+            Self::with_uinput(type_text);
 
             // Handle reopen or exit after typing is done
             if reopen {
@@ -587,6 +1926,53 @@ impl EmoticonPicker {
         });
     }
 
+    /// Wait for the modifier keys to be released, at most half a second
+    ///
+    /// This is important for Shift+Enter to work correctly, as the held
+    /// Shift would change the typed keys.
+    async fn modifiers_released() {
+        let mut wait_count = 0;
+        let max_wait = 10; // 10 * 50ms = 500ms max
+
+        while wait_count < max_wait {
+            glib::timeout_future(std::time::Duration::from_millis(50)).await;
+            wait_count += 1;
+
+            // Check if modifier keys are still pressed
+            let mut has_modifiers = false;
+            if let Some(display) = gtk::gdk::Display::default() {
+                if let Some(seat) = display.default_seat() {
+                    if let Some(device) = seat.keyboard() {
+                        let modifier_state = device.modifier_state();
+                        has_modifiers = modifier_state.contains(gdk::ModifierType::SHIFT_MASK) ||
+                                      modifier_state.contains(gdk::ModifierType::CONTROL_MASK) ||
+                                      modifier_state.contains(gdk::ModifierType::ALT_MASK);
+                    }
+                }
+            }
+
+            if !has_modifiers {
+                break;
+            }
+        }
+    }
+
+    /// Type with a new uinput keyboard, reporting problems on stderr
+    fn with_uinput(type_text: impl FnOnce(&mut UinputKeyboard) -> std::io::Result<()>) {
+        match UinputKeyboard::new() {
+            Ok(mut keyboard) => {
+                if let Err(e) = type_text(&mut keyboard) {
+                    eprintln!("Failed to type emoticon via uinput: {}", e);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to create uinput device: {}", e);
+                eprintln!("Note: uinput requires write access to /dev/uinput or /dev/input/uinput");
+                eprintln!("You may need to add your user to the 'input' group or run with appropriate permissions.");
+            }
+        }
+    }
+
     /// Reopen the window
     fn reopen_window(&self) {
         self.window.set_visible(true);
@@ -606,6 +1992,12 @@ impl EmoticonPicker {
 }
 
 fn main() {
+    // Commands like `smile import` run in the terminal
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     // Create GTK Application
     let app = gtk::Application::builder()
         .application_id("com.github.uliruffler.smile")
@@ -0,0 +1,126 @@
+//! Plain-text emoticon packs
+//!
+//! A pack is a line-based file with one emoticon per line, followed by a tab
+//! and its keywords separated by spaces or commas:
+//!
+//! ```text
+//! # category: Kaomoji: Joy
+//! (* ^ ω ^)<TAB>happy joy smile
+//! ٩(◕‿◕｡)۶<TAB>happy joy cheer
+//! ```
+//!
+//! Lines starting with `# category:` switch the category, other lines
+//! starting with `#` are comments. Without a category line the entries go
+//! into a category named after the file. Packs are read from
+//! `~/.smile/packs/`; the built-in kaomoji library uses the same format.
+
+use crate::custom::{self, CustomEmoticon, CustomError};
+use std::fs;
+use std::path::Path;
+
+/// Load all packs from a directory, in file name order
+///
+/// A missing directory is not an error, it just means there are no packs.
+pub fn load_packs(dir: &Path) -> (Vec<CustomEmoticon>, Vec<CustomError>) {
+    let mut emoticons = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut files: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_file()).collect(),
+        Err(_) => return (emoticons, errors),
+    };
+    files.sort();
+
+    for file in files {
+        match fs::read_to_string(&file) {
+            Ok(content) => {
+                let (mut pack_emoticons, mut pack_errors) = parse_pack(&file, &content);
+                emoticons.append(&mut pack_emoticons);
+                errors.append(&mut pack_errors);
+            }
+            Err(e) => errors.push(CustomError {
+                file: file.clone(),
+                line: 1,
+                message: format!("Failed to read file: {}", e),
+            }),
+        }
+    }
+
+    (emoticons, errors)
+}
+
+/// Parse and validate the content of a pack file
+pub fn parse_pack(path: &Path, content: &str) -> (Vec<CustomEmoticon>, Vec<CustomError>) {
+    let mut category = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| custom::DEFAULT_CUSTOM_CATEGORY.to_string());
+    let mut emoticons: Vec<CustomEmoticon> = Vec::new();
+    let mut errors = Vec::new();
+
+    for (line_no, line) in content.lines().enumerate() {
+        if let Some(name) = line.strip_prefix("# category:") {
+            category = name.trim().to_string();
+            continue;
+        }
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (text, keywords) = line.split_once('\t').unwrap_or((line, ""));
+        let emoticon = CustomEmoticon::new(
+            text.trim_end().to_string(),
+            category.clone(),
+            keywords
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|k| !k.is_empty())
+                .map(|k| k.to_string())
+                .collect(),
+        );
+        match custom::validate(&emoticon, &emoticons) {
+            Some(message) => errors.push(CustomError {
+                file: path.to_path_buf(),
+                line: line_no + 1,
+                message,
+            }),
+            None => emoticons.push(emoticon),
+        }
+    }
+
+    (emoticons, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pack() {
+        let content = "# My cats\n(=^･ω･^=)\tcat, cute\n\n# category: Dogs\n∪･ω･∪\tdog happy\n∪＾ェ＾∪\n";
+        let (emoticons, errors) = parse_pack(Path::new("/packs/cats.txt"), content);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(emoticons.len(), 3);
+        assert_eq!(emoticons[0].category, "cats");
+        assert_eq!(emoticons[0].keywords, vec!["cat", "cute"]);
+        assert_eq!(emoticons[1].category, "Dogs");
+        assert_eq!(emoticons[1].keywords, vec!["dog", "happy"]);
+        assert!(emoticons[2].keywords.is_empty());
+    }
+
+    #[test]
+    fn test_pack_errors_have_lines() {
+        let content = "(^_^)\thappy\n\tno text\n(^_^)\tagain\nok\u{7}\n";
+        let (emoticons, errors) = parse_pack(Path::new("faces.txt"), content);
+        assert_eq!(emoticons.len(), 1);
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+        assert!(errors[0].to_string().starts_with("faces.txt:2: "));
+    }
+
+    #[test]
+    fn test_missing_directory() {
+        let (emoticons, errors) = load_packs(Path::new("/nonexistent/smile/packs"));
+        assert!(emoticons.is_empty());
+        assert!(errors.is_empty());
+    }
+}
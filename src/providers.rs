@@ -0,0 +1,385 @@
+//! Sources of the emoticons shown in the picker
+//!
+//! A provider either has a fixed list of emoticons, which are shown in the
+//! categories and searched by their keywords, or searches by itself and
+//! returns items for a query. The emoji, classic emoticons, Gitmoji,
+//! kaomoji and custom emoticons are providers of the first kind, the
+//! Unicode symbols and the scripts declared in `settings.toml` of the
+//! second:
+//!
+//! ```toml
+//! [[providers]]
+//! name = "Status icons"
+//! command = "~/bin/status-icons"
+//! args = ["--team", "infra"] # optional
+//! timeout_ms = 2000          # optional
+//! ```
+//!
+//! A script gets the search text as a line on stdin and prints a JSON array
+//! of items, either plain strings or objects like
+//! `{"text": "🟢", "name": "Service is up"}`. Scripts are only started once
+//! the search text has stopped changing for a moment, and a script that is
+//! still running when the text changes again is stopped.
+
+use crate::custom::CustomEmoticon;
+use crate::emoticons;
+use crate::gitmoji;
+use crate::symbols;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// An item found by a provider
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Item {
+    /// Just the text that is typed
+    Text(String),
+    /// Text with a name for the tooltip
+    Named { text: String, name: Option<String> },
+}
+
+impl Item {
+    /// Text that is typed
+    pub fn text(&self) -> &str {
+        match self {
+            Item::Text(text) | Item::Named { text, .. } => text,
+        }
+    }
+
+    /// Name shown as tooltip, if any
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Item::Text(_) => None,
+            Item::Named { name, .. } => name.as_deref(),
+        }
+    }
+}
+
+/// Tells a running search that its results are no longer needed
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A source of emoticons
+pub trait Provider: Send + Sync {
+    /// Name of the provider, the title of its search results
+    fn name(&self) -> &str;
+
+    /// Emoticons shown in the categories and searched by their keywords
+    fn emoticons(&self) -> &'static [CustomEmoticon] {
+        &[]
+    }
+
+    /// Categories of the emoticons, in the order they are first listed
+    fn categories(&self) -> Vec<(&'static str, Vec<&'static str>)> {
+        let mut categories = Vec::new();
+        emoticons::merge_custom(&mut categories, self.emoticons());
+        categories
+    }
+
+    /// Time the search text has to stay unchanged before `search()` is called
+    fn debounce(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Items found for a search, for providers that search by themselves
+    ///
+    /// Slow searches stop early, with no items, once they are cancelled.
+    fn search(&self, _query: &str, _cancellation: &Cancellation) -> Result<Vec<Item>, String> {
+        Ok(Vec::new())
+    }
+}
+
+/// The Unicode emoji
+///
+/// Emoji are searched by their CLDR annotations, so they have no keywords
+/// of their own and are listed straight from the emoji table.
+pub struct EmojiProvider;
+
+impl Provider for EmojiProvider {
+    fn name(&self) -> &str {
+        "Emoji"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, Vec<&'static str>)> {
+        emoticons::emoji_categories()
+    }
+}
+
+/// The classic text emoticons like ":-)"
+pub struct ClassicProvider;
+
+impl Provider for ClassicProvider {
+    fn name(&self) -> &str {
+        emoticons::CLASSIC_CATEGORY
+    }
+
+    fn emoticons(&self) -> &'static [CustomEmoticon] {
+        emoticons::classic()
+    }
+}
+
+/// The Gitmoji category
+pub struct GitmojiProvider;
+
+impl Provider for GitmojiProvider {
+    fn name(&self) -> &str {
+        gitmoji::GITMOJI_CATEGORY
+    }
+
+    fn emoticons(&self) -> &'static [CustomEmoticon] {
+        gitmoji::emoticons()
+    }
+}
+
+/// The kaomoji library, one category per mood
+pub struct KaomojiProvider;
+
+impl Provider for KaomojiProvider {
+    fn name(&self) -> &str {
+        "Kaomoji"
+    }
+
+    fn emoticons(&self) -> &'static [CustomEmoticon] {
+        emoticons::kaomoji()
+    }
+}
+
+/// The emoticons of custom.toml and the packs
+pub struct CustomProvider;
+
+impl Provider for CustomProvider {
+    fn name(&self) -> &str {
+        "Custom"
+    }
+
+    fn emoticons(&self) -> &'static [CustomEmoticon] {
+        emoticons::custom_emoticons()
+    }
+}
+
+/// Unicode characters whose names match the search, e.g. "double arrow"
+pub struct SymbolProvider;
+
+impl SymbolProvider {
+    /// Most symbols shown in the search results
+    const LIMIT: usize = 40;
+    /// Shorter searches match too many names to be useful
+    const MIN_QUERY: usize = 3;
+}
+
+impl Provider for SymbolProvider {
+    fn name(&self) -> &str {
+        "Symbols"
+    }
+
+    fn search(&self, query: &str, _cancellation: &Cancellation) -> Result<Vec<Item>, String> {
+        if query.trim().chars().count() < Self::MIN_QUERY {
+            return Ok(Vec::new());
+        }
+        // Emoji are already found through their own annotations
+        Ok(symbols::search(query, usize::MAX)
+            .into_iter()
+            .map(|info| info.ch.to_string())
+            .filter(|text| emoticons::lookup(text).is_none())
+            .take(Self::LIMIT)
+            .map(Item::Text)
+            .collect())
+    }
+}
+
+/// The built-in providers in the order their categories are shown
+pub fn built_in() -> &'static [&'static dyn Provider] {
+    &[&EmojiProvider, &ClassicProvider, &GitmojiProvider, &KaomojiProvider, &CustomProvider, &SymbolProvider]
+}
+
+/// A script provider as declared in `[[providers]]` of settings.toml
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptProvider {
+    /// Title of the search results
+    pub name: String,
+    /// Program to run; "~/" is the home directory
+    pub command: String,
+    /// Arguments of the program
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Time the program may take before it is stopped
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    2000
+}
+
+impl ScriptProvider {
+    /// The program with "~/" resolved
+    fn program(&self) -> PathBuf {
+        match (self.command.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(&self.command),
+        }
+    }
+}
+
+impl Provider for ScriptProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Typing a word would start the script for every letter
+    fn debounce(&self) -> Duration {
+        Duration::from_millis(200)
+    }
+
+    fn search(&self, query: &str, cancellation: &Cancellation) -> Result<Vec<Item>, String> {
+        let fail = |what: String| format!("Provider '{}': {}", self.name, what);
+
+        let mut child = Command::new(self.program())
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| fail(format!("failed to run {}: {}", self.command, e)))?;
+
+        // A script that doesn't read its input closes the pipe early, which is fine
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = writeln!(stdin, "{}", query);
+        }
+        // Read the output while waiting, so a large output can't block the script
+        let stdout = read_all(child.stdout.take());
+        let stderr = read_all(child.stderr.take());
+
+        let start = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if cancellation.is_cancelled() => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Ok(Vec::new());
+                }
+                Ok(None) if start.elapsed() < Duration::from_millis(self.timeout_ms) => {
+                    thread::sleep(Duration::from_millis(10))
+                }
+                Ok(None) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(fail(format!("no answer within {} ms", self.timeout_ms)));
+                }
+                Err(e) => return Err(fail(e.to_string())),
+            }
+        };
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+
+        if !status.success() {
+            let stderr = String::from_utf8_lossy(&stderr);
+            return Err(fail(format!("{}: {}", status, stderr.trim())));
+        }
+        serde_json::from_slice(&stdout).map_err(|e| fail(format!("invalid JSON output: {}", e)))
+    }
+}
+
+/// Read a pipe to its end in a thread
+fn read_all(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        output
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(body: &str) -> ScriptProvider {
+        ScriptProvider {
+            name: "Test".to_string(),
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), body.to_string()],
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+
+    #[test]
+    fn test_script_provider() {
+        let provider = script(r#"read query; printf '["%s", {"text": "🟢", "name": "up"}, {"text": "x"}]' "$query""#);
+        let items = provider.search("status", &Cancellation::default()).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0], Item::Text("status".to_string()));
+        assert_eq!((items[1].text(), items[1].name()), ("🟢", Some("up")));
+        assert_eq!(items[2].name(), None);
+    }
+
+    #[test]
+    fn test_script_provider_errors() {
+        let error = script("echo broken >&2; exit 3").search("", &Cancellation::default()).unwrap_err();
+        assert!(error.starts_with("Provider 'Test'"), "{}", error);
+        assert!(error.contains("broken"), "{}", error);
+
+        assert!(script("echo '{not json'").search("", &Cancellation::default()).unwrap_err().contains("invalid JSON"));
+
+        let mut slow = script("sleep 5");
+        slow.timeout_ms = 100;
+        let start = Instant::now();
+        assert!(slow.search("", &Cancellation::default()).unwrap_err().contains("no answer within 100 ms"));
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        // A newer search stops the script
+        let cancellation = Cancellation::default();
+        let cancel = cancellation.clone();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            cancel.cancel();
+        });
+        let start = Instant::now();
+        assert_eq!(script("sleep 5").search("", &cancellation), Ok(Vec::new()));
+        assert!(start.elapsed() < Duration::from_secs(4));
+        canceller.join().unwrap();
+
+        let mut missing = script("");
+        missing.command = "/nonexistent/smile-provider".to_string();
+        assert!(missing.search("", &Cancellation::default()).unwrap_err().contains("failed to run"));
+    }
+
+    #[test]
+    fn test_settings() {
+        let provider: ScriptProvider = toml::from_str("name = \"Status\"\ncommand = \"~/bin/status\"\n").unwrap();
+        assert_eq!(provider.timeout_ms, 2000);
+        assert!(provider.args.is_empty());
+        assert!(provider.program().is_absolute() || dirs::home_dir().is_none());
+        assert!(toml::from_str::<ScriptProvider>("name = \"x\"\ncommand = \"y\"\nargv = []\n").is_err());
+    }
+
+    #[test]
+    fn test_symbol_provider() {
+        let items = SymbolProvider.search("double arrow", &Cancellation::default()).unwrap();
+        assert!(items.iter().any(|item| item.text() == "⇒"));
+        assert!(items.len() <= SymbolProvider::LIMIT);
+        assert!(SymbolProvider.search("ar", &Cancellation::default()).unwrap().is_empty());
+        // Emoji are left to the categories
+        let items = SymbolProvider.search("black heart suit", &Cancellation::default()).unwrap();
+        assert!(!items.iter().any(|item| item.text() == "♥"));
+    }
+}
@@ -0,0 +1,227 @@
+//! Emoji as PNG or SVG images, for `smile render` and the picker's Image menu
+//!
+//! The text is laid out with Pango in the emoji font of the settings and
+//! drawn with Cairo on the CPU through pangocairo, scaled so its ink fills
+//! the image up to the padding. No display is needed, so `smile render`
+//! works over SSH and in scripts.
+
+use gtk::{cairo, gdk, pango};
+use std::path::Path;
+
+/// Font used when `emoji_font` isn't set; fontconfig resolves it to the
+/// preferred color emoji font
+pub const DEFAULT_FONT: &str = "emoji";
+
+/// Font size the text is laid out in before it is scaled to the image
+const LAYOUT_SIZE: f64 = 256.0;
+
+/// Largest image that is rendered, in pixels
+pub const MAX_SIZE: u32 = 4096;
+
+/// Image format, from the file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Svg,
+}
+
+impl Format {
+    /// Format of a file, e.g. PNG for "party.png"
+    pub fn of(path: &Path) -> Result<Format, String> {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+            Some("png") => Ok(Format::Png),
+            Some("svg") => Ok(Format::Svg),
+            _ => Err(format!("{}: unknown image format, expected a .png or .svg file", path.display())),
+        }
+    }
+}
+
+/// How an image is rendered
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Width and height in pixels
+    pub size: u32,
+    /// Empty space around the emoji, in pixels
+    pub padding: u32,
+    /// Background color; transparent if None
+    pub background: Option<gdk::RGBA>,
+    /// Font family, e.g. "Noto Color Emoji"
+    pub font: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            size: 256,
+            padding: 0,
+            background: None,
+            font: DEFAULT_FONT.to_string(),
+        }
+    }
+}
+
+impl Options {
+    /// Check the size and the padding
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size == 0 || self.size > MAX_SIZE {
+            Err(format!("Size {} is out of range, expected 1 to {}", self.size, MAX_SIZE))
+        } else if self.padding.saturating_mul(2) >= self.size {
+            Err(format!("Padding {} leaves no room in a {} pixel image", self.padding, self.size))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Scale and offset that fit an ink rectangle into the image, centered
+///
+/// The ink rectangle is given as x, y, width and height in layout pixels.
+fn fit(ink: (f64, f64, f64, f64), size: u32, padding: u32) -> (f64, f64, f64) {
+    let (x, y, width, height) = ink;
+    let room = f64::from(size - 2 * padding);
+    let scale = room / width.max(height);
+    let dx = (f64::from(size) - width * scale) / 2.0 - x * scale;
+    let dy = (f64::from(size) - height * scale) / 2.0 - y * scale;
+    (scale, dx, dy)
+}
+
+/// Draw a text onto a Cairo context of `options.size` pixels
+fn draw(cr: &cairo::Context, text: &str, options: &Options) -> Result<(), String> {
+    let layout = pangocairo::functions::create_layout(cr);
+    let mut font = pango::FontDescription::new();
+    font.set_family(&options.font);
+    font.set_absolute_size(LAYOUT_SIZE * f64::from(pango::SCALE));
+    layout.set_font_description(Some(&font));
+    layout.set_text(text);
+
+    let (ink, _) = layout.pixel_extents();
+    if ink.width() <= 0 || ink.height() <= 0 {
+        return Err(format!("'{}' has nothing to draw", text));
+    }
+    let (scale, dx, dy) = fit(
+        (f64::from(ink.x()), f64::from(ink.y()), f64::from(ink.width()), f64::from(ink.height())),
+        options.size,
+        options.padding,
+    );
+
+    if let Some(background) = options.background {
+        cr.set_source_rgba(
+            f64::from(background.red()),
+            f64::from(background.green()),
+            f64::from(background.blue()),
+            f64::from(background.alpha()),
+        );
+        cr.paint().map_err(|e| e.to_string())?;
+    }
+
+    // Color glyphs keep their colors, the rest is drawn in black
+    cr.set_source_rgb(0.0, 0.0, 0.0);
+    cr.translate(dx, dy);
+    cr.scale(scale, scale);
+    pangocairo::functions::update_layout(cr, &layout);
+    pangocairo::functions::show_layout(cr, &layout);
+    cr.status().map_err(|e| e.to_string())
+}
+
+/// Render a text as a PNG image
+pub fn render_png(text: &str, options: &Options) -> Result<Vec<u8>, String> {
+    options.validate()?;
+    let size = options.size as i32;
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, size, size).map_err(|e| e.to_string())?;
+    let cr = cairo::Context::new(&surface).map_err(|e| e.to_string())?;
+    draw(&cr, text, options)?;
+    drop(cr);
+
+    let mut png = Vec::new();
+    surface.write_to_png(&mut png).map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+/// Render a text into a PNG or SVG file, depending on its extension
+pub fn render_to_file(text: &str, options: &Options, path: &Path) -> Result<(), String> {
+    let fail = |e: String| format!("Failed to write {}: {}", path.display(), e);
+    match Format::of(path)? {
+        Format::Png => {
+            let png = render_png(text, options)?;
+            std::fs::write(path, png).map_err(|e| fail(e.to_string()))
+        }
+        Format::Svg => {
+            options.validate()?;
+            let size = f64::from(options.size);
+            let surface = cairo::SvgSurface::new(size, size, Some(path)).map_err(|e| fail(e.to_string()))?;
+            let cr = cairo::Context::new(&surface).map_err(|e| fail(e.to_string()))?;
+            draw(&cr, text, options)?;
+            drop(cr);
+            surface.finish();
+            surface.status().map_err(|e| fail(e.to_string()))
+        }
+    }
+}
+
+/// File name offered for an emoji, e.g. "party-popper.png"
+pub fn file_name(text: &str, name: Option<&str>, format: Format) -> String {
+    let stem: String = match name {
+        Some(name) => name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect::<String>()
+            .split('-')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-"),
+        None => text.chars().map(|c| format!("{:x}", c as u32)).collect::<Vec<_>>().join("-"),
+    };
+    let extension = match format {
+        Format::Png => "png",
+        Format::Svg => "svg",
+    };
+    format!("{}.{}", stem, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Format::of(Path::new("party.PNG")), Ok(Format::Png));
+        assert_eq!(Format::of(Path::new("slides/party.svg")), Ok(Format::Svg));
+        assert!(Format::of(Path::new("party.jpg")).is_err());
+        assert!(Format::of(Path::new("party")).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Options::default().validate().is_ok());
+        let options = |size, padding| Options {
+            size,
+            padding,
+            ..Options::default()
+        };
+        assert!(options(0, 0).validate().is_err());
+        assert!(options(MAX_SIZE + 1, 0).validate().is_err());
+        assert!(options(64, 31).validate().is_ok());
+        assert!(options(64, 32).validate().is_err());
+        // Doesn't overflow
+        assert!(options(64, u32::MAX).validate().is_err());
+    }
+
+    #[test]
+    fn test_fit() {
+        // A wide glyph fills the width and is centered vertically
+        let (scale, dx, dy) = fit((10.0, 20.0, 200.0, 100.0), 100, 0);
+        assert_eq!(scale, 0.5);
+        assert_eq!((dx, dy), (-5.0, 15.0));
+
+        let (scale, dx, dy) = fit((0.0, 0.0, 100.0, 100.0), 100, 10);
+        assert_eq!(scale, 0.8);
+        assert_eq!((dx, dy), (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("🎉", Some("party popper"), Format::Png), "party-popper.png");
+        assert_eq!(file_name("👍🏽", Some("thumbs up: medium skin tone"), Format::Svg), "thumbs-up-medium-skin-tone.svg");
+        assert_eq!(file_name(":-)", None, Format::Png), "3a-2d-29.png");
+    }
+}
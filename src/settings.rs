@@ -3,8 +3,16 @@
 //! This module handles loading and saving settings from ~/.smile/settings.toml
 //! and recently used emoticons from ~/.smile/recent.json
 
+use crate::annotations;
+use crate::emoticons::{self, EmojiVersion, SkinTone};
+use crate::filters;
+use crate::gitmoji;
+use crate::latex;
+use crate::providers::ScriptProvider;
+use crate::shortcodes::{self, ShortcodeSet};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
@@ -15,48 +23,97 @@ const DEFAULT_SETTINGS_TOML: &str = r#"# Smile Emoticon Picker Settings
 # Maximum number of recently used emoticons to keep
 max_recent = 10
 
+# Skin tone applied to emoji that support one (hands, people, ...)
+# One of "none", "light", "medium-light", "medium", "medium-dark", "dark"
+# Right-click or long-press an emoji to pick a different tone
+default_skin_tone = "none"
+
+# Only offer emoji introduced up to this Emoji version, e.g. for friends on older phones
+# Recently used emoji above it are marked; without the setting all emoji are offered
+# max_emoji_version = "13.0"
+
+# Emoji that the installed fonts can't render (shown as boxes)
+# "hide" removes them, "mark" greys them out, "show" shows them as they are
+unsupported_glyphs = "hide"
+
+# Languages for searching emoji by their CLDR names and keywords, e.g. ["de", "en"]
+# When empty, the language of the environment ($LANG) and English are used
+search_locales = []
+
+# Shortcode table for searching with ":tada:" and for inserting shortcodes
+# One of "github", "slack", "discord"
+# Ctrl+Enter or Ctrl+click types the shortcode of an emoji instead of the emoji
+shortcodes = "github"
+
+# Gitmoji category with the meaning of each emoji, e.g. ":bug: Fix a bug."
+# "glyph" types the emoji, "code" types its code (":bug:"), "off" hides the category
+gitmoji = "glyph"
+
+# Images from ~/.smile/images/ are put on the clipboard as PNG, as they can't be typed
+# "off" leaves pasting to you, "ctrl-v", "ctrl-shift-v" or "shift-insert" pastes right away
+image_paste = "off"
+
+# Font of the images made by `smile render` and the Image menu of an emoji
+# Without it, fontconfig picks the preferred color emoji font
+# emoji_font = "Noto Color Emoji"
+
+# Programs that add their own search results, e.g. an internal list of status icons
+# Each gets the search text as a line on stdin and prints a JSON array of items:
+# ["✅", {"text": "🟢", "name": "Service is up"}]
+# [[providers]]
+# name = "Status icons"
+# command = "~/bin/status-icons"
+# args = []
+# timeout_ms = 2000
+
+# Layout of the emoticon categories
+# Category names are the ones from the Unicode data, e.g. "Smileys & Emotion"
+[layout]
+# Display order of the categories; unlisted categories follow in the default order
+order = [
+    "Smileys & Emotion", "People & Body", "Animals & Nature", "Food & Drink", "Travel & Places",
+    "Activities", "Objects", "Symbols", "Flags", "Classic",
+]
+# Categories and single emoticons that are not shown
+hidden_categories = []
+hidden_emoticons = []
+
+# Rename categories, e.g. "Smileys & Emotion" = "Faces"
+[layout.rename]
+
+# Move emoticons to another (possibly new) category, e.g. "🚀" = "Work"
+[layout.move]
+
 # Keywords for emoticon categories
 # These keywords are used for searching emoticons
 [keywords]
 
-[keywords.happy]
-terms = ["happy", "joy", "smile", "grin", "cheerful", "glad", "pleased"]
-
-[keywords.laughing]
-terms = ["laugh", "lol", "lmao", "rofl", "haha", "hehe", "funny", "hilarious", "tears", "crying", "joy"]
-
-[keywords.sad]
-terms = ["sad", "cry", "unhappy", "depressed", "down", "tear", "weep"]
+[keywords."smileys & emotion"]
+terms = ["smiley", "emotion", "expression"]
 
-[keywords.angry]
-terms = ["angry", "mad", "furious", "rage", "annoyed", "irritated"]
+[keywords."people & body"]
+terms = ["people", "person", "body", "gesture"]
 
-[keywords.surprised]
-terms = ["surprised", "shocked", "amazed", "astonished", "wow"]
+[keywords."animals & nature"]
+terms = ["animal", "nature", "pet", "plant"]
 
-[keywords.love]
-terms = ["love", "heart", "romance", "affection", "adore", "crush"]
+[keywords."food & drink"]
+terms = ["food", "drink", "eat", "beverage"]
 
-[keywords.gestures]
-terms = ["hand", "gesture", "thumbs", "clap", "wave", "point", "fist", "ok", "peace"]
+[keywords."travel & places"]
+terms = ["travel", "place", "transport", "vehicle", "weather"]
 
-[keywords.faces]
-terms = ["face", "expression", "neutral", "tired", "sleepy", "bored"]
-
-[keywords.cool]
-terms = ["cool", "awesome", "sunglasses", "cat", "kitty", "nerd", "smart"]
-
-[keywords.symbols]
-terms = ["star", "fire", "sparkle", "lightning", "check", "cross", "rainbow"]
+[keywords.activities]
+terms = ["activity", "sport", "game", "event"]
 
 [keywords.objects]
-terms = ["party", "celebration", "gift", "trophy", "medal", "award", "balloon"]
+terms = ["object", "thing", "tool"]
 
-[keywords.animals]
-terms = ["animal", "pet", "dog", "cat", "mouse", "bear", "fox", "lion", "monkey"]
+[keywords.symbols]
+terms = ["symbol", "sign"]
 
-[keywords.food]
-terms = ["food", "eat", "pizza", "burger", "coffee", "cake", "dessert", "snack"]
+[keywords.flags]
+terms = ["flag", "country", "nation"]
 
 [keywords.classic]
 terms = ["classic", "text", "ascii", "emoticon", "smiley", "shrug", "tableflip"]
@@ -305,9 +362,6 @@ terms = ["cat", "grinning", "happy", "smile"]
 [emoticon_keywords."😸"]
 terms = ["cat", "grin", "smile", "happy"]
 
-[emoticon_keywords."😹"]
-terms = ["cat", "joy", "tears", "laugh"]
-
 [emoticon_keywords."😻"]
 terms = ["cat", "heart", "eyes", "love"]
 
@@ -580,6 +634,46 @@ pub struct Settings {
     #[serde(default = "default_max_recent")]
     pub max_recent: usize,
 
+    /// Skin tone applied to modifier-capable emoji when they are typed
+    #[serde(default)]
+    pub default_skin_tone: SkinTone,
+
+    /// Newest Emoji version that is offered, e.g. 13.0
+    #[serde(default)]
+    pub max_emoji_version: Option<EmojiVersion>,
+
+    /// How emoji the installed fonts can't render are shown
+    #[serde(default)]
+    pub unsupported_glyphs: UnsupportedGlyphs,
+
+    /// Languages of the CLDR annotations used for searching
+    #[serde(default)]
+    pub search_locales: Vec<String>,
+
+    /// Chat application whose shortcodes are searched and inserted
+    #[serde(default)]
+    pub shortcodes: ShortcodeSet,
+
+    /// What is typed for entries of the Gitmoji category
+    #[serde(default)]
+    pub gitmoji: GitmojiMode,
+
+    /// Shortcut sent after an image is put on the clipboard
+    #[serde(default)]
+    pub image_paste: PasteShortcut,
+
+    /// Font family emoji images are rendered in
+    #[serde(default)]
+    pub emoji_font: Option<String>,
+
+    /// Programs that search by themselves
+    #[serde(default)]
+    pub providers: Vec<ScriptProvider>,
+
+    /// Category order, names, moved and hidden emoticons
+    #[serde(default)]
+    pub layout: Layout,
+
     /// Keywords for each emoticon category
     #[serde(default)]
     pub keywords: HashMap<String, CategoryKeywords>,
@@ -598,20 +692,215 @@ pub struct WindowState {
     pub y: i32,
 }
 
+/// How emoji that the installed fonts can't render are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnsupportedGlyphs {
+    /// Leave them out of the categories
+    #[default]
+    Hide,
+    /// Show them greyed out
+    Mark,
+    /// Show them as they are, without checking the fonts
+    Show,
+}
+
+/// What is typed for entries of the Gitmoji category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitmojiMode {
+    /// The emoji, e.g. 🐛
+    #[default]
+    Glyph,
+    /// The code, e.g. ":bug:"
+    Code,
+    /// Don't show the Gitmoji category
+    Off,
+}
+
+/// Shortcut that pastes the clipboard in the focused application
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PasteShortcut {
+    /// Don't paste, the user does
+    #[default]
+    Off,
+    CtrlV,
+    /// Terminals paste with Ctrl+Shift+V
+    CtrlShiftV,
+    ShiftInsert,
+}
+
+/// Cached glyph coverage for one font configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlyphCache {
+    /// Fingerprint of the fonts and emoticons the coverage was computed for
+    pub fingerprint: String,
+    /// Emoticons that can't be rendered
+    pub unsupported: Vec<String>,
+}
+
 fn default_max_recent() -> usize {
     10
 }
 
+/// User-controlled layout of the emoticon categories
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Layout {
+    /// Category display order; unlisted categories follow in the default order
+    #[serde(default)]
+    pub order: Vec<String>,
+
+    /// Display names for categories
+    #[serde(default)]
+    pub rename: HashMap<String, String>,
+
+    /// Target category for single emoticons
+    #[serde(default, rename = "move")]
+    pub moves: HashMap<String, String>,
+
+    /// Categories that are not shown
+    #[serde(default)]
+    pub hidden_categories: Vec<String>,
+
+    /// Emoticons that are not shown
+    #[serde(default)]
+    pub hidden_emoticons: Vec<String>,
+}
+
+/// A category as shown in the picker
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutCategory {
+    /// Category name, used for keyword lookups and in the layout settings
+    pub name: String,
+    /// Name shown in the picker
+    pub label: String,
+    pub emoticons: Vec<&'static str>,
+}
+
+impl Layout {
+    /// Arrange the given categories according to the layout
+    ///
+    /// Category names are compared case-insensitively; emoticons are compared
+    /// without variation selectors, so "❤" and "❤️" are the same.
+    pub fn apply(&self, categories: &[(&str, Vec<&'static str>)]) -> Vec<LayoutCategory> {
+        fn emoticon_key(emoticon: &str) -> String {
+            emoticon.replace('\u{FE0F}', "")
+        }
+
+        let hidden_emoticons: Vec<String> = self.hidden_emoticons.iter().map(|e| emoticon_key(e)).collect();
+        let moves: HashMap<String, &String> = self.moves.iter().map(|(e, c)| (emoticon_key(e), c)).collect();
+
+        // Default order, with categories created by moves at the end
+        let mut result: Vec<LayoutCategory> = categories
+            .iter()
+            .map(|(name, _)| LayoutCategory {
+                name: name.to_string(),
+                label: name.to_string(),
+                emoticons: Vec::new(),
+            })
+            .collect();
+        let find = |result: &[LayoutCategory], name: &str| {
+            result.iter().position(|c| c.name.eq_ignore_ascii_case(name))
+        };
+
+        // Moved emoticons are appended after the category's own ones
+        let mut moved: Vec<(&str, &'static str)> = Vec::new();
+        for (index, (_, emoticons)) in categories.iter().enumerate() {
+            for &emoticon in emoticons {
+                let key = emoticon_key(emoticon);
+                if hidden_emoticons.contains(&key) {
+                    continue;
+                }
+                match moves.get(&key) {
+                    Some(target) => moved.push((target.as_str(), emoticon)),
+                    None => result[index].emoticons.push(emoticon),
+                }
+            }
+        }
+        for (target, emoticon) in moved {
+            let index = find(&result, target).unwrap_or_else(|| {
+                result.push(LayoutCategory {
+                    name: target.to_string(),
+                    label: target.to_string(),
+                    emoticons: Vec::new(),
+                });
+                result.len() - 1
+            });
+            result[index].emoticons.push(emoticon);
+        }
+
+        // Listed categories first, in the given order
+        let mut ordered: Vec<LayoutCategory> = Vec::with_capacity(result.len());
+        for name in &self.order {
+            if let Some(index) = find(&result, name) {
+                ordered.push(result.remove(index));
+            }
+        }
+        ordered.append(&mut result);
+
+        ordered.retain(|c| {
+            !c.emoticons.is_empty() && !self.hidden_categories.iter().any(|h| h.eq_ignore_ascii_case(&c.name))
+        });
+        for category in &mut ordered {
+            if let Some((_, label)) = self.rename.iter().find(|(name, _)| name.eq_ignore_ascii_case(&category.name)) {
+                category.label = label.clone();
+            }
+        }
+        ordered
+    }
+}
+
 /// Keywords for a category
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "Terms")]
 pub struct CategoryKeywords {
     pub terms: Vec<String>,
+    /// The terms in lowercase, for the search
+    #[serde(skip)]
+    search_terms: Vec<String>,
 }
 
 /// Keywords for an individual emoticon
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "Terms")]
 pub struct EmoticonKeywords {
     pub terms: Vec<String>,
+    /// The terms in lowercase, for the search
+    #[serde(skip)]
+    search_terms: Vec<String>,
+}
+
+/// Keywords as written in settings.toml
+#[derive(Deserialize)]
+struct Terms {
+    terms: Vec<String>,
+}
+
+impl From<Terms> for CategoryKeywords {
+    fn from(keywords: Terms) -> Self {
+        CategoryKeywords::new(keywords.terms)
+    }
+}
+
+impl From<Terms> for EmoticonKeywords {
+    fn from(keywords: Terms) -> Self {
+        EmoticonKeywords::new(keywords.terms)
+    }
+}
+
+impl CategoryKeywords {
+    pub fn new(terms: Vec<String>) -> Self {
+        let search_terms = terms.iter().map(|t| t.to_lowercase()).collect();
+        CategoryKeywords { terms, search_terms }
+    }
+}
+
+impl EmoticonKeywords {
+    pub fn new(terms: Vec<String>) -> Self {
+        let search_terms = terms.iter().map(|t| t.to_lowercase()).collect();
+        EmoticonKeywords { terms, search_terms }
+    }
 }
 
 impl Default for Settings {
@@ -619,18 +908,135 @@ impl Default for Settings {
         // Parse default settings from TOML
         toml::from_str(DEFAULT_SETTINGS_TOML).unwrap_or_else(|_| Settings {
             max_recent: 10,
+            default_skin_tone: SkinTone::None,
+            max_emoji_version: None,
+            unsupported_glyphs: UnsupportedGlyphs::Hide,
+            search_locales: Vec::new(),
+            shortcodes: ShortcodeSet::Github,
+            gitmoji: GitmojiMode::Glyph,
+            image_paste: PasteShortcut::Off,
+            emoji_font: None,
+            providers: Vec::new(),
+            layout: Layout::default(),
             keywords: HashMap::new(),
             emoticon_keywords: HashMap::new(),
         })
     }
 }
 
+impl Settings {
+    /// Check if an emoji is newer than `max_emoji_version`
+    ///
+    /// Sequences of several emoji, e.g. a favorite like "🎉🫠", are as new as
+    /// their newest emoji. Text emoticons and other entries without an Emoji
+    /// version never are.
+    pub fn exceeds_max_emoji_version(&self, emoticon: &str) -> bool {
+        let Some(max) = self.max_emoji_version else {
+            return false;
+        };
+        if let Some(entry) = emoticons::lookup(emoticon) {
+            return entry.version > max;
+        }
+        let mut rest = emoticon;
+        while let Some(c) = rest.chars().next() {
+            match filters::emoji_at(rest) {
+                Some((entry, _)) if entry.version > max => return true,
+                Some((_, len)) => rest = &rest[len..],
+                None => rest = &rest[c.len_utf8()..],
+            }
+        }
+        false
+    }
+
+    /// Arrange categories as the picker shows them
+    ///
+    /// The `[layout]` is applied, emoji newer than `max_emoji_version` are
+    /// left out and so is the Gitmoji category if it is switched off.
+    pub fn categories(&self, categories: &[(&str, Vec<&'static str>)]) -> Vec<LayoutCategory> {
+        let mut categories = self.layout.apply(categories);
+        for category in &mut categories {
+            category.emoticons.retain(|e| !self.exceeds_max_emoji_version(e));
+        }
+        if self.gitmoji == GitmojiMode::Off {
+            categories.retain(|c| c.name != gitmoji::GITMOJI_CATEGORY);
+        }
+        categories.retain(|c| !c.emoticons.is_empty());
+        categories
+    }
+}
+
 /// Recent emoticons structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RecentEmoticons {
     pub emoticons: Vec<String>,
 }
 
+/// Outcome of merging imported keywords into settings.toml
+#[derive(Debug, Default, PartialEq)]
+pub struct KeywordMerge {
+    /// Emoticons that got new keywords
+    pub updated: usize,
+    /// Keywords that were added
+    pub added: usize,
+    /// Keywords that were skipped because the search already finds the emoticon by them
+    pub known: usize,
+    /// Emoticons whose keywords in settings.toml couldn't be updated in place,
+    /// e.g. because they are written as an inline table
+    pub conflicts: Vec<String>,
+}
+
+/// Header of an emoticon's table in settings.toml, e.g. `[emoticon_keywords."😀"]`
+fn emoticon_keywords_header(emoticon: &str) -> String {
+    format!("[emoticon_keywords.{}]", toml::Value::String(emoticon.to_string()))
+}
+
+fn terms_line(terms: &[String]) -> String {
+    let terms = terms.iter().cloned().map(toml::Value::String).collect();
+    format!("terms = {}", toml::Value::Array(terms))
+}
+
+/// Add a table for an emoticon's keywords at the end of settings.toml
+fn append_emoticon_keywords(content: &str, emoticon: &str, terms: &[String]) -> String {
+    let mut content = content.to_string();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&format!("\n{}\n{}\n", emoticon_keywords_header(emoticon), terms_line(terms)));
+    content
+}
+
+/// Replace the `terms` line of an emoticon's table in settings.toml
+///
+/// Returns None if the table or a single-line `terms` list can't be found.
+fn replace_emoticon_keywords(content: &str, emoticon: &str, terms: &[String]) -> Option<String> {
+    let header = emoticon_keywords_header(emoticon);
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let start = lines.iter().position(|l| l.trim() == header)? + 1;
+    let offset = lines[start..]
+        .iter()
+        .take_while(|l| !l.trim_start().starts_with('['))
+        .position(|l| {
+            let l = l.trim();
+            l.strip_prefix("terms").is_some_and(|rest| rest.trim_start().starts_with('=')) && l.ends_with(']')
+        })?;
+    lines[start + offset] = terms_line(terms);
+
+    let mut edited = lines.join("\n");
+    if content.ends_with('\n') {
+        edited.push('\n');
+    }
+    Some(edited)
+}
+
+/// A text in lowercase, allocated only if it has uppercase letters
+fn lowercase(text: &str) -> Cow<'_, str> {
+    if text.chars().any(char::is_uppercase) {
+        Cow::Owned(text.to_lowercase())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
 /// Configuration manager
 pub struct Config {
     #[allow(dead_code)]
@@ -639,7 +1045,9 @@ pub struct Config {
     settings_file: PathBuf,
     recent_file: PathBuf,
     window_state_file: PathBuf,
+    glyph_cache_file: PathBuf,
     settings: Settings,
+    search_locales: Vec<String>,
 }
 
 impl Config {
@@ -654,6 +1062,7 @@ impl Config {
         let settings_file = config_dir.join("settings.toml");
         let recent_file = config_dir.join("recent.json");
         let window_state_file = config_dir.join("window_state.json");
+        let glyph_cache_file = config_dir.join("glyph_cache.json");
 
         // Create directory if it doesn't exist
         fs::create_dir_all(&config_dir)?;
@@ -665,13 +1074,16 @@ impl Config {
 
         // Load settings
         let settings = Self::load_settings(&settings_file)?;
+        let search_locales = Self::resolve_search_locales(&settings);
 
         Ok(Config {
             config_dir,
             settings_file,
             recent_file,
             window_state_file,
+            glyph_cache_file,
             settings,
+            search_locales,
         })
     }
 
@@ -686,15 +1098,30 @@ impl Config {
         })
     }
 
+    /// Get the search locales from the settings, or from the environment if none are set
+    fn resolve_search_locales(settings: &Settings) -> Vec<String> {
+        if settings.search_locales.is_empty() {
+            annotations::locales_from_env()
+        } else {
+            settings.search_locales.iter().map(|l| l.to_lowercase()).collect()
+        }
+    }
+
     /// Get settings
     pub fn settings(&self) -> &Settings {
         &self.settings
     }
 
+    /// Languages of the CLDR annotations, from the settings or the environment
+    pub fn search_locales(&self) -> &[String] {
+        &self.search_locales
+    }
+
     /// Reload settings from file
     #[allow(dead_code)]
     pub fn reload_settings(&mut self) -> std::io::Result<()> {
         self.settings = Self::load_settings(&self.settings_file)?;
+        self.search_locales = Self::resolve_search_locales(&self.settings);
         Ok(())
     }
 
@@ -720,6 +1147,85 @@ impl Config {
         Ok(())
     }
 
+    /// Add imported emoticons after the recently used ones
+    ///
+    /// The list is cut to `max_recent`, but existing entries are never
+    /// dropped. Returns how many emoticons were added.
+    pub fn merge_recent(&self, imported: &[String]) -> std::io::Result<usize> {
+        let mut recent = self.load_recent();
+        let before = recent.len();
+        for emoticon in imported {
+            if !recent.contains(emoticon) {
+                recent.push(emoticon.clone());
+            }
+        }
+        recent.truncate(self.settings.max_recent.max(before));
+
+        let added = recent.len() - before;
+        if added > 0 {
+            self.save_recent(&recent)?;
+        }
+        Ok(added)
+    }
+
+    /// Merge imported keywords into `[emoticon_keywords]` of settings.toml
+    ///
+    /// Keywords the search already finds an emoticon by are skipped; the
+    /// user's own keywords are kept and the new ones added after them.
+    /// settings.toml is edited in place so that its comments survive. Each
+    /// emoticon is expected at most once in `imported`.
+    pub fn merge_emoticon_keywords(&mut self, imported: &[(String, Vec<String>)]) -> std::io::Result<KeywordMerge> {
+        let mut content = fs::read_to_string(&self.settings_file)?;
+        let mut merge = KeywordMerge::default();
+
+        for (emoticon, terms) in imported {
+            let mut new_terms: Vec<String> = Vec::new();
+            for term in terms {
+                let term_lower = term.to_lowercase();
+                if self.matches_emoticon_keywords(emoticon, &term_lower)
+                    || new_terms.iter().any(|t| t.to_lowercase() == term_lower)
+                {
+                    merge.known += 1;
+                } else {
+                    new_terms.push(term.clone());
+                }
+            }
+            if new_terms.is_empty() {
+                continue;
+            }
+
+            let edited = match self.settings.emoticon_keywords.get(emoticon) {
+                Some(existing) => {
+                    let all: Vec<String> = existing.terms.iter().chain(&new_terms).cloned().collect();
+                    replace_emoticon_keywords(&content, emoticon, &all)
+                }
+                None => Some(append_emoticon_keywords(&content, emoticon, &new_terms)),
+            };
+            match edited {
+                Some(edited) => {
+                    content = edited;
+                    merge.updated += 1;
+                    merge.added += new_terms.len();
+                }
+                None => merge.conflicts.push(emoticon.clone()),
+            }
+        }
+        if merge.updated == 0 {
+            return Ok(merge);
+        }
+
+        // Only replace the file if the edited settings still parse
+        let settings: Settings = toml::from_str(&content).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to update settings.toml: {}", e),
+            )
+        })?;
+        fs::write(&self.settings_file, &content)?;
+        self.settings = settings;
+        Ok(merge)
+    }
+
     /// Migrate from old config location (~/.config/smile/history.json)
     fn migrate_from_old_config(&self) -> Vec<String> {
         if let Some(config_dir) = dirs::config_dir() {
@@ -743,19 +1249,17 @@ impl Config {
 
     /// Check if a search query matches any keywords for a category
     pub fn matches_category_keywords(&self, category: &str, query: &str) -> bool {
-        let query_lower = query.to_lowercase();
+        let query_lower = lowercase(query);
 
         // Check category name itself
-        if category.to_lowercase().contains(&query_lower) {
+        if lowercase(category).contains(&*query_lower) {
             return true;
         }
 
         // Check keywords
         if let Some(keywords) = self.settings.keywords.get(&category.to_lowercase()) {
-            for term in &keywords.terms {
-                if term.to_lowercase().contains(&query_lower) {
-                    return true;
-                }
+            if keywords.search_terms.iter().any(|term| term.contains(&*query_lower)) {
+                return true;
             }
         }
 
@@ -763,28 +1267,78 @@ impl Config {
     }
 
     /// Check if a search query matches any keywords for an emoticon
+    ///
+    /// Runs for every emoticon on every keystroke, so everything it compares
+    /// with is in lowercase already and nothing is allocated for the emoji.
     pub fn matches_emoticon_keywords(&self, emoticon: &str, query: &str) -> bool {
-        let query_lower = query.to_lowercase();
+        // A backslash switches to exact LaTeX commands, e.g. `\leftrightarrow` for ↔️;
+        // they are case-sensitive, `\Leftrightarrow` is ⇔
+        if let Some(command) = latex::parse_query(query) {
+            return latex::matches(emoticon, command);
+        }
+
+        let query_lower = lowercase(query);
+        let query_lower = query_lower.as_ref();
 
         // Check the emoticon itself
-        if emoticon.to_lowercase().contains(&query_lower) {
+        if lowercase(emoticon).contains(query_lower) {
             return true;
         }
 
         // Check keywords for this specific emoticon
         if let Some(keywords) = self.settings.emoticon_keywords.get(emoticon) {
-            for term in &keywords.terms {
-                if term.to_lowercase().contains(&query_lower) {
+            if keywords.search_terms.iter().any(|term| term.contains(query_lower)) {
+                return true;
+            }
+        }
+
+        // Check the shortcodes, also when written as ":tada:"
+        if let Some(code) = shortcodes::parse_query(query_lower) {
+            if shortcodes::shortcodes_of(self.settings.shortcodes, emoticon)
+                .iter()
+                .any(|s| s.contains(code))
+            {
+                return true;
+            }
+        }
+
+        // Flags also match their ISO 3166 code, e.g. "de", "gb-sct" or "gbsct"
+        if let Some(code) = emoticons::search_flag_code(emoticon) {
+            if code == query_lower || code.chars().filter(|&c| c != '-').eq(query_lower.chars()) {
+                return true;
+            }
+        }
+
+        // Gitmoji match their code and meaning, e.g. "fix a bug" for 🐛
+        if self.settings.gitmoji != GitmojiMode::Off && gitmoji::matches(emoticon, query_lower) {
+            return true;
+        }
+
+        // Check the CLDR names and keywords in the search languages
+        for locale in &self.search_locales {
+            if let Some(annotation) = annotations::get(locale, emoticon) {
+                if annotation.search_terms.iter().any(|t| t.contains(query_lower)) {
                     return true;
                 }
             }
         }
 
+        // Check keywords of kaomoji, custom.toml and packs
+        if emoticons::extra_keywords(emoticon).iter().any(|k| k.contains(query_lower)) {
+            return true;
+        }
+
+        // Check the Unicode (CLDR) name of the emoji
+        if let Some(entry) = emoticons::lookup(emoticon) {
+            if entry.search_name.contains(query_lower) {
+                return true;
+            }
+        }
+
         false
     }
 
     /// Get configuration directory path
-    #[allow(dead_code)]
     pub fn config_dir(&self) -> &PathBuf {
         &self.config_dir
     }
@@ -805,11 +1359,51 @@ impl Config {
         fs::write(&self.window_state_file, json)?;
         Ok(())
     }
+
+    /// Load the emoticons the fonts can't render, if cached for this font configuration
+    pub fn load_glyph_cache(&self, fingerprint: &str) -> Option<HashSet<String>> {
+        let content = fs::read_to_string(&self.glyph_cache_file).ok()?;
+        let cache = serde_json::from_str::<GlyphCache>(&content).ok()?;
+        (cache.fingerprint == fingerprint).then(|| cache.unsupported.into_iter().collect())
+    }
+
+    /// Save the emoticons the fonts can't render for a font configuration
+    pub fn save_glyph_cache(&self, fingerprint: &str, unsupported: &HashSet<String>) -> std::io::Result<()> {
+        let mut unsupported: Vec<String> = unsupported.iter().cloned().collect();
+        unsupported.sort();
+        let cache = GlyphCache {
+            fingerprint: fingerprint.to_string(),
+            unsupported,
+        };
+        let json = serde_json::to_string_pretty(&cache)?;
+        fs::write(&self.glyph_cache_file, json)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A configuration in a directory of its own below the temp directory, which isn't created
+    fn test_config(settings: Settings, locales: &[&str]) -> Config {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let config_dir = std::env::temp_dir().join(format!(
+            "smile_test_{}_{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        Config {
+            settings_file: config_dir.join("settings.toml"),
+            recent_file: config_dir.join("recent.json"),
+            window_state_file: config_dir.join("window_state.json"),
+            glyph_cache_file: config_dir.join("glyph_cache.json"),
+            config_dir,
+            settings,
+            search_locales: locales.iter().map(|l| l.to_string()).collect(),
+        }
+    }
 
     #[test]
     fn test_default_settings() {
@@ -832,6 +1426,328 @@ terms = ["happy", "joy"]
         let settings: Settings = toml::from_str(toml_content).unwrap();
         assert_eq!(settings.max_recent, 15);
         assert!(settings.keywords.contains_key("happy"));
+        assert_eq!(settings.default_skin_tone, SkinTone::None);
+    }
+
+    #[test]
+    fn test_providers_parse() {
+        assert!(Settings::default().providers.is_empty());
+
+        let settings: Settings = toml::from_str(
+            r#"
+[[providers]]
+name = "Status icons"
+command = "~/bin/status-icons"
+
+[[providers]]
+name = "Glossary"
+command = "glossary"
+args = ["--json"]
+timeout_ms = 500
+"#,
+        )
+        .unwrap();
+        assert_eq!(settings.providers.len(), 2);
+        assert_eq!(settings.providers[0].timeout_ms, 2000);
+        assert_eq!(settings.providers[1].args, vec!["--json"]);
+    }
+
+    #[test]
+    fn test_default_skin_tone_parse() {
+        let settings: Settings = toml::from_str(r#"default_skin_tone = "medium-dark""#).unwrap();
+        assert_eq!(settings.default_skin_tone, SkinTone::MediumDark);
+
+        assert!(toml::from_str::<Settings>(r#"default_skin_tone = "purple""#).is_err());
+    }
+
+    #[test]
+    fn test_layout_parse() {
+        let settings = Settings::default();
+        assert_eq!(settings.layout.order.first().map(String::as_str), Some("Smileys & Emotion"));
+        assert!(settings.layout.hidden_categories.is_empty());
+
+        let toml_content = r#"
+[layout]
+order = ["Classic", "Flags"]
+hidden_emoticons = ["🍆"]
+
+[layout.rename]
+"Smileys & Emotion" = "Faces"
+
+[layout.move]
+"🚀" = "Work"
+        "#;
+        let settings: Settings = toml::from_str(toml_content).unwrap();
+        assert_eq!(settings.layout.order, vec!["Classic", "Flags"]);
+        assert_eq!(settings.layout.rename.get("Smileys & Emotion").map(String::as_str), Some("Faces"));
+        assert_eq!(settings.layout.moves.get("🚀").map(String::as_str), Some("Work"));
+    }
+
+    #[test]
+    fn test_layout_apply() {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let categories = vec![
+            ("Smileys", vec!["😀", "😂", "❤️"]),
+            ("Travel", vec!["🚀", "🚗"]),
+            ("Flags", vec!["🏁"]),
+            ("Classic", vec![":-)"]),
+        ];
+
+        // The default layout keeps everything as it is
+        let unchanged = Layout::default().apply(&categories);
+        let names: Vec<&str> = unchanged.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(names, vec!["Smileys", "Travel", "Flags", "Classic"]);
+
+        let layout = Layout {
+            order: strings(&["classic", "Work", "Unknown"]),
+            rename: [("Smileys".to_string(), "Faces".to_string())].into_iter().collect(),
+            moves: [("🚀".to_string(), "Work".to_string()), ("❤".to_string(), "Classic".to_string())]
+                .into_iter()
+                .collect(),
+            hidden_categories: strings(&["Flags"]),
+            hidden_emoticons: strings(&["🚗", "😂"]),
+        };
+        let result = layout.apply(&categories);
+
+        let names: Vec<&str> = result.iter().map(|c| c.label.as_str()).collect();
+        // Travel is empty after moving and hiding, Flags is hidden
+        assert_eq!(names, vec!["Classic", "Work", "Faces"]);
+        assert_eq!(result[0].emoticons, vec![":-)", "❤️"]);
+        assert_eq!(result[1].emoticons, vec!["🚀"]);
+        assert_eq!(result[2].name, "Smileys");
+        assert_eq!(result[2].emoticons, vec!["😀"]);
+    }
+
+    #[test]
+    fn test_max_emoji_version() {
+        assert_eq!(Settings::default().max_emoji_version, None);
+        assert!(!Settings::default().exceeds_max_emoji_version("🫠"));
+
+        let settings: Settings = toml::from_str(r#"max_emoji_version = "13.0""#).unwrap();
+        assert_eq!(settings.max_emoji_version, Some(EmojiVersion { major: 13, minor: 0 }));
+        assert!(settings.exceeds_max_emoji_version("🫠")); // E14.0
+        assert!(!settings.exceeds_max_emoji_version("🥲")); // E13.0
+        assert!(!settings.exceeds_max_emoji_version("😀"));
+        assert!(!settings.exceeds_max_emoji_version(":-)"));
+        // Favorites and recents with several emoji
+        assert!(settings.exceeds_max_emoji_version("🎉🫠"));
+        assert!(settings.exceeds_max_emoji_version("yay 🫠!"));
+        assert!(!settings.exceeds_max_emoji_version("🎉🥲"));
+
+        let settings: Settings = toml::from_str(r#"max_emoji_version = "12""#).unwrap();
+        assert!(settings.exceeds_max_emoji_version("🥲"));
+        assert!(toml::from_str::<Settings>(r#"max_emoji_version = "latest""#).is_err());
+    }
+
+    #[test]
+    fn test_categories() {
+        let categories = vec![
+            ("Smileys", vec!["🫠", "🥲"]),
+            ("Other", vec!["🫠"]),
+            (gitmoji::GITMOJI_CATEGORY, vec!["🐛"]),
+        ];
+        let settings: Settings = toml::from_str("max_emoji_version = \"13.0\"\ngitmoji = \"off\"").unwrap();
+        let result = settings.categories(&categories);
+        let names: Vec<&str> = result.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Smileys"]);
+        assert_eq!(result[0].emoticons, vec!["🥲"]);
+    }
+
+    #[test]
+    fn test_unsupported_glyphs_parse() {
+        assert_eq!(Settings::default().unsupported_glyphs, UnsupportedGlyphs::Hide);
+        let settings: Settings = toml::from_str(r#"unsupported_glyphs = "mark""#).unwrap();
+        assert_eq!(settings.unsupported_glyphs, UnsupportedGlyphs::Mark);
+    }
+
+    #[test]
+    fn test_glyph_cache() {
+        let config = test_config(Settings::default(), &["en"]);
+        fs::create_dir_all(config.config_dir()).unwrap();
+
+        assert!(config.load_glyph_cache("fonts-a").is_none());
+
+        let unsupported: HashSet<String> = ["🫩".to_string(), "🫆".to_string()].into_iter().collect();
+        config.save_glyph_cache("fonts-a", &unsupported).unwrap();
+        assert_eq!(config.load_glyph_cache("fonts-a"), Some(unsupported));
+
+        // A different font configuration invalidates the cache
+        assert!(config.load_glyph_cache("fonts-b").is_none());
+
+        fs::remove_dir_all(config.config_dir()).ok();
+    }
+
+    #[test]
+    fn test_shortcodes_parse() {
+        assert_eq!(Settings::default().shortcodes, ShortcodeSet::Github);
+        let settings: Settings = toml::from_str(r#"shortcodes = "slack""#).unwrap();
+        assert_eq!(settings.shortcodes, ShortcodeSet::Slack);
+        assert!(toml::from_str::<Settings>(r#"shortcodes = "irc""#).is_err());
+    }
+
+    #[test]
+    fn test_shortcode_matching() {
+        let mut config = test_config(Settings::default(), &["en"]);
+
+        assert!(config.matches_emoticon_keywords("🎉", ":tada:"));
+        assert!(config.matches_emoticon_keywords("🎉", ":tad"));
+        assert!(config.matches_emoticon_keywords("🎉", "tada"));
+        assert!(config.matches_emoticon_keywords("👍", ":+1:"));
+        assert!(!config.matches_emoticon_keywords("🎉", ":+1:"));
+        // Classic emoticons are still found by their text
+        assert!(config.matches_emoticon_keywords(":-)", ":-)"));
+
+        config.settings.shortcodes = ShortcodeSet::Discord;
+        assert!(config.matches_emoticon_keywords("🇩🇪", ":flag_de:"));
+        assert!(!config.matches_emoticon_keywords("🇩🇪", ":flag-de:"));
+    }
+
+    #[test]
+    fn test_gitmoji_matching() {
+        let mut config = test_config(Settings::default(), &["en"]);
+
+        assert_eq!(config.settings.gitmoji, GitmojiMode::Glyph);
+        assert!(config.matches_emoticon_keywords("🐛", "fix a bug"));
+        assert!(config.matches_emoticon_keywords("♻️", "Refactor"));
+        assert!(config.matches_emoticon_keywords("🚑", ":ambulance:"));
+
+        config.settings = toml::from_str(r#"gitmoji = "off""#).unwrap();
+        assert_eq!(config.settings.gitmoji, GitmojiMode::Off);
+        assert!(!config.matches_emoticon_keywords("♻️", "refactor"));
+        assert!(toml::from_str::<Settings>(r#"gitmoji = "both""#).is_err());
+    }
+
+    #[test]
+    fn test_image_paste_parse() {
+        assert_eq!(Settings::default().image_paste, PasteShortcut::Off);
+        let settings: Settings = toml::from_str(r#"image_paste = "ctrl-shift-v""#).unwrap();
+        assert_eq!(settings.image_paste, PasteShortcut::CtrlShiftV);
+        assert!(toml::from_str::<Settings>(r#"image_paste = "ctrl+v""#).is_err());
+    }
+
+    #[test]
+    fn test_latex_matching() {
+        let config = test_config(Settings::default(), &["en"]);
+
+        assert!(config.matches_emoticon_keywords("↔️", "\\leftrightarrow"));
+        assert!(config.matches_emoticon_keywords("♠️", "\\spadesuit"));
+        // The command has to be complete and nothing else is searched
+        assert!(!config.matches_emoticon_keywords("↔️", "\\leftright"));
+        assert!(!config.matches_emoticon_keywords("↔️", "\\arrow"));
+        assert!(!config.matches_emoticon_keywords("↔️", "\\Leftrightarrow"));
+        assert!(!config.matches_emoticon_keywords("↑", "\\Uparrow"));
+        assert!(config.matches_emoticon_keywords("↔️", "arrow"));
+    }
+
+    #[test]
+    fn test_search_locales() {
+        let settings: Settings = toml::from_str(r#"search_locales = ["DE", "en"]"#).unwrap();
+        assert_eq!(Config::resolve_search_locales(&settings), vec!["de", "en"]);
+
+        // Without the setting English is always searched
+        assert!(Config::resolve_search_locales(&Settings::default()).contains(&"en".to_string()));
+    }
+
+    #[test]
+    fn test_multilingual_keyword_matching() {
+        let mut settings = Settings::default();
+        settings.emoticon_keywords.insert(
+            "🍺".to_string(),
+            EmoticonKeywords::new(vec!["feierabend".to_string()]),
+        );
+        let config = test_config(settings, &["de", "fr"]);
+
+        assert!(config.matches_emoticon_keywords("😂", "freudentränen"));
+        assert!(config.matches_emoticon_keywords("😂", "RIANT"));
+        assert!(config.matches_emoticon_keywords("🍺", "prost"));
+        // The user's own keywords are still used
+        assert!(config.matches_emoticon_keywords("🍺", "feierabend"));
+        // Locales that are not selected are not searched
+        assert!(!config.matches_emoticon_keywords("🍺", "cerveza"));
+    }
+
+    #[test]
+    fn test_flag_matching() {
+        let config = test_config(Settings::default(), &["de", "en"]);
+
+        assert!(config.matches_emoticon_keywords("🇩🇪", "de"));
+        assert!(config.matches_emoticon_keywords("🇩🇪", "DE"));
+        assert!(config.matches_emoticon_keywords("🇩🇪", "germany"));
+        assert!(config.matches_emoticon_keywords("🇩🇪", "deutschland"));
+        assert!(config.matches_emoticon_keywords("🇯🇵", "flagge"));
+        assert!(!config.matches_emoticon_keywords("🇫🇷", "de"));
+
+        // Subdivision flags
+        assert!(config.matches_emoticon_keywords("🏴󠁧󠁢󠁳󠁣󠁴󠁿", "gb-sct"));
+        assert!(config.matches_emoticon_keywords("🏴󠁧󠁢󠁳󠁣󠁴󠁿", "gbsct"));
+        assert!(config.matches_emoticon_keywords("🏴󠁧󠁢󠁳󠁣󠁴󠁿", "schottland"));
+        assert!(config.matches_emoticon_keywords("🏴󠁧󠁢󠁷󠁬󠁳󠁿", "wales"));
+    }
+
+    #[test]
+    fn test_recent_flag_sequences() {
+        let config = test_config(Settings::default(), &["en"]);
+        fs::create_dir_all(config.config_dir()).unwrap();
+
+        let recent = vec!["🏴󠁧󠁢󠁥󠁮󠁧󠁿".to_string(), "🇩🇪".to_string(), "👩🏽‍💻".to_string()];
+        config.save_recent(&recent).unwrap();
+        assert_eq!(config.load_recent(), recent);
+
+        fs::remove_dir_all(config.config_dir()).ok();
+    }
+
+    #[test]
+    fn test_merge_recent() {
+        let mut config = test_config(Settings::default(), &["en"]);
+        fs::create_dir_all(config.config_dir()).unwrap();
+        config.settings.max_recent = 3;
+
+        config.save_recent(&["🚀".to_string(), "👍".to_string()]).unwrap();
+        let imported = vec!["👍".to_string(), "🎉".to_string(), "🐛".to_string()];
+        assert_eq!(config.merge_recent(&imported).unwrap(), 1);
+        assert_eq!(config.load_recent(), vec!["🚀", "👍", "🎉"]);
+        assert_eq!(config.merge_recent(&imported).unwrap(), 0);
+
+        fs::remove_dir_all(config.config_dir()).ok();
+    }
+
+    #[test]
+    fn test_merge_emoticon_keywords() {
+        let mut config = test_config(Settings::default(), &["en"]);
+        fs::create_dir_all(config.config_dir()).unwrap();
+        let settings_file = config.settings_file.clone();
+        fs::write(&settings_file, DEFAULT_SETTINGS_TOML).unwrap();
+
+        let imported = vec![
+            // "happy" is already a keyword of 😀, "grinning" is its name
+            ("😀".to_string(), vec!["happy".to_string(), "Grinning".to_string(), "cheerful".to_string()]),
+            ("🚀".to_string(), vec!["rocket".to_string(), "ship it".to_string(), "launch".to_string()]),
+            ("(ツ)".to_string(), vec!["shrug".to_string()]),
+        ];
+        let merge = config.merge_emoticon_keywords(&imported).unwrap();
+        assert_eq!(merge.updated, 3);
+        assert_eq!(merge.added, 4);
+        assert_eq!(merge.known, 3);
+        assert!(merge.conflicts.is_empty());
+
+        // The file keeps its comments and the search finds the new keywords
+        let content = fs::read_to_string(&settings_file).unwrap();
+        assert!(content.starts_with("# Smile Emoticon Picker Settings"));
+        assert!(content.contains("terms = [\"grinning\", \"smile\", \"happy\", \"cheerful\"]"));
+        assert!(config.matches_emoticon_keywords("🚀", "ship it"));
+        assert!(config.matches_emoticon_keywords("(ツ)", "shrug"));
+        assert_eq!(Config::load_settings(&settings_file).unwrap().emoticon_keywords["🚀"].terms, ["ship it", "launch"]);
+
+        // Importing again adds nothing
+        assert_eq!(config.merge_emoticon_keywords(&imported).unwrap().updated, 0);
+
+        // Keywords written as an inline table can't be updated in place
+        fs::write(&settings_file, "[emoticon_keywords]\n\"🎉\" = { terms = [\"party\"] }\n").unwrap();
+        config.reload_settings().unwrap();
+        let merge = config.merge_emoticon_keywords(&[("🎉".to_string(), vec!["ship it".to_string()])]).unwrap();
+        assert_eq!(merge.conflicts, vec!["🎉"]);
+
+        fs::remove_dir_all(config.config_dir()).ok();
     }
 
     #[test]
@@ -849,17 +1765,7 @@ terms = ["happy", "joy"]
     #[test]
     fn test_emoticon_keyword_matching() {
         // Create a minimal config for testing
-        let settings = Settings::default();
-        let config_dir = std::env::temp_dir().join("smile_test");
-        let settings_file = config_dir.join("settings.toml");
-        let recent_file = config_dir.join("recent.json");
-
-        let config = Config {
-            config_dir,
-            settings_file,
-            recent_file,
-            settings,
-        };
+        let config = test_config(Settings::default(), &["en"]);
 
         // Test that fire emoji matches "fire" keyword
         assert!(config.matches_emoticon_keywords("🔥", "fire"));
@@ -875,5 +1781,10 @@ terms = ["happy", "joy"]
 
         // Test partial matching
         assert!(config.matches_emoticon_keywords("🔥", "fir"));
+
+        // Test matching by Unicode name for emoji without configured keywords
+        assert!(config.matches_emoticon_keywords("🐛", "bug"));
+        assert!(config.matches_emoticon_keywords("🚀", "rocket"));
+        assert!(!config.matches_emoticon_keywords("🚀", "pizza"));
     }
 }
@@ -0,0 +1,197 @@
+//! Chat shortcodes like `:tada:` and `:+1:`
+//!
+//! The tables for GitHub, Slack and Discord are vendored in
+//! `data/shortcodes/<set>.txt`, one emoji per line followed by a tab and its
+//! shortcodes without colons. The first shortcode of an emoji is the one
+//! that is inserted.
+
+use crate::emoticons::{self, SkinTone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// The chat application whose shortcodes are used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShortcodeSet {
+    #[default]
+    Github,
+    Slack,
+    Discord,
+}
+
+impl ShortcodeSet {
+    /// All shortcode sets
+    pub const ALL: [ShortcodeSet; 3] = [ShortcodeSet::Github, ShortcodeSet::Slack, ShortcodeSet::Discord];
+
+    /// Name in the settings and on the command line, e.g. "slack"
+    pub fn name(self) -> &'static str {
+        match self {
+            ShortcodeSet::Github => "github",
+            ShortcodeSet::Slack => "slack",
+            ShortcodeSet::Discord => "discord",
+        }
+    }
+
+    fn data(self) -> &'static str {
+        match self {
+            ShortcodeSet::Github => include_str!("../data/shortcodes/github.txt"),
+            ShortcodeSet::Slack => include_str!("../data/shortcodes/slack.txt"),
+            ShortcodeSet::Discord => include_str!("../data/shortcodes/discord.txt"),
+        }
+    }
+
+    /// Shortcode suffix for a skin tone, if the chat application has one
+    fn skin_tone_suffix(self, tone: SkinTone) -> Option<String> {
+        let index = SkinTone::ALL.iter().position(|&t| t == tone).filter(|&i| i > 0)?;
+        match self {
+            ShortcodeSet::Github => None,
+            // Slack counts the light tone as 2, like the Fitzpatrick scale
+            ShortcodeSet::Slack => Some(format!("::skin-tone-{}", index + 1)),
+            ShortcodeSet::Discord => Some(format!("_tone{}", index)),
+        }
+    }
+}
+
+impl FromStr for ShortcodeSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ShortcodeSet::ALL
+            .into_iter()
+            .find(|set| set.name() == s.to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = ShortcodeSet::ALL.iter().map(|set| set.name()).collect();
+                format!("unknown shortcode set '{}', expected one of {}", s, names.join(", "))
+            })
+    }
+}
+
+/// Shortcode lookups of one set
+struct Table {
+    /// Emoji of each shortcode, as written in the table
+    emoji: HashMap<&'static str, &'static str>,
+    /// Shortcodes of each emoji without variation selectors
+    shortcodes: HashMap<String, Vec<&'static str>>,
+}
+
+static TABLES: OnceLock<HashMap<ShortcodeSet, Table>> = OnceLock::new();
+
+fn table(set: ShortcodeSet) -> &'static Table {
+    let tables = TABLES.get_or_init(|| ShortcodeSet::ALL.iter().map(|&set| (set, parse_table(set.data()))).collect());
+    &tables[&set]
+}
+
+fn parse_table(content: &'static str) -> Table {
+    let mut table = Table {
+        emoji: HashMap::new(),
+        shortcodes: HashMap::new(),
+    };
+    for line in content.lines().filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let (emoji, codes) = line.split_once('\t').unwrap_or((line, ""));
+        let codes: Vec<&'static str> = codes.split_whitespace().collect();
+        for code in &codes {
+            table.emoji.entry(*code).or_insert(emoji);
+        }
+        table.shortcodes.insert(emoji.replace('\u{FE0F}', ""), codes);
+    }
+    table
+}
+
+/// Shortcode written in a search query, without the colons
+///
+/// Accepts ":tada:", ":tada" and "tada"; returns None when the text can't
+/// be a shortcode, e.g. for ":-)".
+pub fn parse_query(query: &str) -> Option<&str> {
+    let code = query.strip_prefix(':').unwrap_or(query);
+    let code = code.strip_suffix(':').unwrap_or(code);
+    let valid = !code.is_empty()
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'));
+    valid.then_some(code)
+}
+
+/// Shortcodes of an emoji without colons, e.g. ["+1", "thumbsup"] for 👍
+pub fn shortcodes_of(set: ShortcodeSet, emoji: &str) -> &'static [&'static str] {
+    let codes = table(set).shortcodes.get(emoticons::without_variation_selectors(emoji).as_ref());
+    codes.map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Emoji with exactly this shortcode, given with or without colons
+pub fn emoji_for(set: ShortcodeSet, query: &str) -> Option<&'static str> {
+    let code = parse_query(query)?.to_lowercase();
+    let emoji = *table(set).emoji.get(code.as_str())?;
+    // Prefer the fully-qualified form that is shown in the picker
+    emoticons::fully_qualified(emoji).or(Some(emoji))
+}
+
+/// Shortcode to insert for an emoji, e.g. ":tada:"
+///
+/// Emoji with a skin tone get the tone suffix of the chat application, e.g.
+/// ":+1::skin-tone-4:" on Slack. Returns None if the emoji has no shortcode
+/// in the set.
+pub fn shortcode(set: ShortcodeSet, emoji: &str) -> Option<String> {
+    if let Some(code) = shortcodes_of(set, emoji).first() {
+        return Some(format!(":{}:", code));
+    }
+
+    let tone = emoticons::skin_tone_of(emoji).filter(|&t| t != SkinTone::None)?;
+    let base = emoticons::with_skin_tone(emoji, SkinTone::None)?;
+    let code = shortcodes_of(set, base).first()?;
+    Some(format!(":{}{}:", code, set.skin_tone_suffix(tone)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(parse_query(":tada:"), Some("tada"));
+        assert_eq!(parse_query(":+1"), Some("+1"));
+        assert_eq!(parse_query("flag-de"), Some("flag-de"));
+        assert_eq!(parse_query(":-)"), None);
+        assert_eq!(parse_query("::"), None);
+        assert_eq!(parse_query("party popper"), None);
+    }
+
+    #[test]
+    fn test_emoji_for() {
+        assert_eq!(emoji_for(ShortcodeSet::Github, ":tada:"), Some("🎉"));
+        assert_eq!(emoji_for(ShortcodeSet::Github, ":+1:"), Some("👍"));
+        assert_eq!(emoji_for(ShortcodeSet::Github, "TADA"), Some("🎉"));
+        // The picker shows the fully-qualified heart
+        assert_eq!(emoji_for(ShortcodeSet::Github, ":heart:"), Some("❤️"));
+        assert_eq!(emoji_for(ShortcodeSet::Slack, ":flag-de:"), Some("🇩🇪"));
+        assert_eq!(emoji_for(ShortcodeSet::Discord, ":flag_de:"), Some("🇩🇪"));
+        assert_eq!(emoji_for(ShortcodeSet::Github, ":flag_de:"), None);
+        assert_eq!(emoji_for(ShortcodeSet::Github, ":no_such_code:"), None);
+    }
+
+    #[test]
+    fn test_shortcode() {
+        assert_eq!(shortcode(ShortcodeSet::Github, "🎉").as_deref(), Some(":tada:"));
+        assert_eq!(shortcode(ShortcodeSet::Github, "❤️").as_deref(), Some(":heart:"));
+        assert_eq!(shortcode(ShortcodeSet::Discord, "🙂").as_deref(), Some(":slight_smile:"));
+        assert_eq!(shortcode(ShortcodeSet::Github, ":-)"), None);
+
+        // Skin tones
+        assert_eq!(shortcode(ShortcodeSet::Slack, "👍🏽").as_deref(), Some(":+1::skin-tone-4:"));
+        assert_eq!(shortcode(ShortcodeSet::Discord, "👍🏻").as_deref(), Some(":thumbsup_tone1:"));
+        assert_eq!(shortcode(ShortcodeSet::Github, "👍🏽"), None);
+    }
+
+    #[test]
+    fn test_tables_have_no_duplicates() {
+        for set in ShortcodeSet::ALL {
+            let mut seen = std::collections::HashSet::new();
+            for line in set.data().lines().filter(|l| !l.starts_with('#')) {
+                for code in line.split('\t').nth(1).unwrap_or_default().split_whitespace() {
+                    assert!(seen.insert(code), "{:?}: duplicate shortcode '{}'", set, code);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Unicode character browser - arrows, math operators, currency signs, ...
+//!
+//! The characters and blocks are generated at build time from the vendored
+//! `data/UnicodeData.txt` and `data/Blocks.txt` (see `build.rs`). Characters
+//! can be browsed by block or by general category and searched by their
+//! official name, e.g. "rightwards double arrow" for ⇒.
+
+/// A named Unicode character
+#[derive(Debug, PartialEq)]
+pub struct CharInfo {
+    pub ch: char,
+    /// Official name, e.g. "RIGHTWARDS DOUBLE ARROW"
+    pub name: &'static str,
+    /// General category, e.g. "Sm"
+    pub category: &'static str,
+}
+
+/// A Unicode block, e.g. "Arrows" from U+2190 to U+21FF
+#[derive(Debug, PartialEq)]
+pub struct Block {
+    pub first: u32,
+    pub last: u32,
+    pub name: &'static str,
+}
+
+include!(concat!(env!("OUT_DIR"), "/symbols_data.rs"));
+
+/// General categories with their names, in the order they are offered
+pub const CATEGORIES: &[(&str, &str)] = &[
+    ("Sm", "Math symbol"),
+    ("Sc", "Currency symbol"),
+    ("So", "Other symbol"),
+    ("Sk", "Modifier symbol"),
+    ("Pd", "Dash punctuation"),
+    ("Ps", "Open punctuation"),
+    ("Pe", "Close punctuation"),
+    ("Pi", "Initial quote punctuation"),
+    ("Pf", "Final quote punctuation"),
+    ("Pc", "Connector punctuation"),
+    ("Po", "Other punctuation"),
+    ("Nd", "Decimal number"),
+    ("Nl", "Letter number"),
+    ("No", "Other number"),
+    ("Lu", "Uppercase letter"),
+    ("Ll", "Lowercase letter"),
+    ("Lt", "Titlecase letter"),
+    ("Lm", "Modifier letter"),
+    ("Lo", "Other letter"),
+    ("Mn", "Nonspacing mark"),
+    ("Mc", "Spacing mark"),
+    ("Me", "Enclosing mark"),
+    ("Zs", "Space separator"),
+    ("Zl", "Line separator"),
+    ("Zp", "Paragraph separator"),
+    ("Cf", "Format character"),
+];
+
+impl Block {
+    /// The named characters of the block
+    pub fn characters(&self) -> &'static [CharInfo] {
+        let start = CHARACTERS.partition_point(|c| (c.ch as u32) < self.first);
+        let end = CHARACTERS.partition_point(|c| (c.ch as u32) <= self.last);
+        &CHARACTERS[start..end]
+    }
+}
+
+/// Blocks that have at least one named character
+pub fn blocks() -> impl Iterator<Item = &'static Block> {
+    BLOCKS.iter().filter(|b| !b.characters().is_empty())
+}
+
+/// Characters of a general category, e.g. "Sm"
+pub fn in_category(category: &str) -> Vec<&'static CharInfo> {
+    CHARACTERS.iter().filter(|c| c.category == category).collect()
+}
+
+/// Look up the name and category of a character
+pub fn lookup(ch: char) -> Option<&'static CharInfo> {
+    CHARACTERS.binary_search_by_key(&ch, |c| c.ch).ok().map(|i| &CHARACTERS[i])
+}
+
+/// Search characters by name
+///
+/// Every word of the query has to appear in the name. The exact name comes
+/// first, then names starting with the query as a whole word, then the
+/// rest; shorter names come first within each group. At most `limit`
+/// characters are returned.
+pub fn search(query: &str, limit: usize) -> Vec<&'static CharInfo> {
+    let query = query.trim().to_uppercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<&'static CharInfo> = CHARACTERS
+        .iter()
+        .filter(|c| words.iter().all(|w| c.name.contains(w)))
+        .collect();
+    let starts_with_word = |name: &str| {
+        name.strip_prefix(query.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+    };
+    matches.sort_by_key(|c| (c.name != query, !starts_with_word(c.name), c.name.len()));
+    matches.truncate(limit);
+    matches
+}
+
+/// Text shown for a character in the browser
+///
+/// Combining marks are shown on a dotted circle, spaces and invisible
+/// format characters by their code point.
+pub fn label(info: &CharInfo) -> String {
+    match info.category {
+        "Mn" | "Mc" | "Me" => format!("\u{25CC}{}", info.ch),
+        "Zs" | "Zl" | "Zp" | "Cf" => format!("U+{:04X}", info.ch as u32),
+        _ => info.ch.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let info = lookup('⇒').unwrap();
+        assert_eq!(info.name, "RIGHTWARDS DOUBLE ARROW");
+        assert_eq!(info.category, "Sm");
+        assert_eq!(lookup('€').map(|c| c.category), Some("Sc"));
+        // Controls and private use characters have no name
+        assert!(lookup('\u{7}').is_none());
+        assert!(lookup('\u{E000}').is_none());
+    }
+
+    #[test]
+    fn test_search() {
+        let results = search("rightwards double arrow", 10);
+        assert_eq!(results[0].ch, '⇒');
+        assert!(results.iter().all(|c| c.name.contains("RIGHTWARDS") && c.name.contains("DOUBLE")));
+
+        // Words may come in any order
+        assert!(search("arrow double rightwards", 100).iter().any(|c| c.ch == '⇒'));
+        assert_eq!(search("box drawings light horizontal", 1)[0].ch, '─');
+        assert_eq!(search("euro", 5)[0].ch, '€');
+        assert!(search("", 10).is_empty());
+        assert_eq!(search("arrow", 20).len(), 20);
+    }
+
+    fn block(name: &str) -> Option<&'static Block> {
+        BLOCKS.iter().find(|b| b.name == name)
+    }
+
+    #[test]
+    fn test_blocks() {
+        let arrows = block("Arrows").unwrap();
+        assert_eq!((arrows.first, arrows.last), (0x2190, 0x21FF));
+        assert_eq!(arrows.characters().len(), 112);
+        assert!(block("Dingbats").unwrap().characters().iter().any(|c| c.ch == '✔'));
+        // Private use areas have no named characters
+        assert!(blocks().all(|b| b.name != "Private Use Area"));
+    }
+
+    #[test]
+    fn test_categories() {
+        let currency = in_category("Sc");
+        assert!(currency.iter().any(|c| c.ch == '$'));
+        assert!(currency.iter().any(|c| c.ch == '₿'));
+        for (category, _) in CATEGORIES {
+            assert!(!in_category(category).is_empty(), "no characters in {}", category);
+        }
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(label(lookup('→').unwrap()), "→");
+        assert_eq!(label(lookup('\u{301}').unwrap()), "\u{25CC}\u{301}");
+        assert_eq!(label(lookup('\u{200D}').unwrap()), "U+200D");
+    }
+}
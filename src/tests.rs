@@ -3,21 +3,27 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::emoticons::EMOTICONS;
+    use crate::custom::CustomEmoticon;
+    use crate::packs::parse_pack;
+    use std::path::Path;
+    use crate::emoticons::{
+        extra_keywords, flag_code, fully_qualified, search_flag_code, without_variation_selectors, get_category, get_emoticons, kaomoji, lookup, merge_custom, skin_tone_of, skin_tone_variants, supports_skin_tone, with_skin_tone, EmojiVersion, Qualification,
+        SkinTone, EMOJI_DATA,
+    };
 
     #[test]
     fn test_emoticons_exist() {
         // Test that emoticons dictionary is populated
-        assert!(!EMOTICONS.is_empty());
+        assert!(!get_emoticons().is_empty());
     }
 
     #[test]
     fn test_emoticon_categories() {
         // Test that expected categories exist
-        let expected_categories = vec!["Happy", "Sad", "Love", "Gestures", "Classic"];
+        let expected_categories = vec!["Smileys & Emotion", "People & Body", "Flags", "Classic"];
         for category in expected_categories {
             assert!(
-                EMOTICONS.contains_key(category),
+                get_category(category).is_some(),
                 "Category '{}' is missing",
                 category
             );
@@ -27,7 +33,7 @@ mod tests {
     #[test]
     fn test_emoticons_are_vecs() {
         // Test that each category contains a vector of emoticons
-        for (category, emoticons) in EMOTICONS.iter() {
+        for (category, emoticons) in get_emoticons().iter() {
             assert!(
                 !emoticons.is_empty(),
                 "Category '{}' has no emoticons",
@@ -39,7 +45,7 @@ mod tests {
     #[test]
     fn test_emoticons_are_strings() {
         // Test that emoticons are strings
-        for (category, emoticons) in EMOTICONS.iter() {
+        for (category, emoticons) in get_emoticons().iter() {
             for emoticon in emoticons {
                 assert!(
                     !emoticon.is_empty(),
@@ -54,40 +60,101 @@ mod tests {
     fn test_all_categories_present() {
         // Test that all expected categories are present
         let required_categories = vec![
-            "Happy", "Sad", "Angry", "Surprised", "Love", "Gestures", "Faces", "Cool", "Symbols",
-            "Objects", "Animals", "Food", "Classic",
+            "Smileys & Emotion", "People & Body", "Animals & Nature", "Food & Drink", "Travel & Places",
+            "Activities", "Objects", "Symbols", "Flags", "Classic",
         ];
         for category in required_categories {
             assert!(
-                EMOTICONS.contains_key(category),
+                get_category(category).is_some(),
                 "Category '{}' is missing",
                 category
             );
         }
+
+        // Components are not offered as a category of their own
+        assert!(get_category("Component").is_none());
     }
 
     #[test]
     fn test_classic_emoticons() {
         // Test that classic text emoticons are included
-        let classic = EMOTICONS.get("Classic").expect("Classic category missing");
+        let classic = get_category("Classic").expect("Classic category missing");
 
         // Check for some common classic emoticons
-        assert!(classic.contains(&":-)".to_string()));
-        assert!(classic.contains(&":)".to_string()));
-        assert!(classic.contains(&"<3".to_string()));
+        assert!(classic.contains(&":-)"));
+        assert!(classic.contains(&":)"));
+        assert!(classic.contains(&"<3"));
+    }
+
+    #[test]
+    fn test_category_order() {
+        // Categories come in CLDR order, followed by classic emoticons, Gitmoji and kaomoji
+        let names: Vec<&str> = get_emoticons().iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names[..10],
+            [
+                "Smileys & Emotion", "People & Body", "Animals & Nature", "Food & Drink", "Travel & Places",
+                "Activities", "Objects", "Symbols", "Flags", "Classic",
+            ]
+        );
+        assert_eq!(names[10], "Gitmoji");
+        assert!(names[11..].iter().all(|name| name.starts_with("Kaomoji: ")));
+        assert_eq!(get_category("Smileys & Emotion").unwrap()[0], "😀");
+    }
+
+    #[test]
+    fn test_kaomoji_library() {
+        let (library, errors) = parse_pack(Path::new("data/kaomoji.txt"), include_str!("../data/kaomoji.txt"));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(library.len(), kaomoji().len());
+        assert!(library.len() >= 300);
+
+        // Every kaomoji can be found by a keyword
+        for entry in library.iter() {
+            assert!(!entry.keywords.is_empty(), "'{}' has no keywords", entry.text);
+        }
+
+        for mood in ["Joy", "Sorrow", "Anger", "Hugging", "Table flips"] {
+            let category = format!("Kaomoji: {}", mood);
+            let emoticons = get_category(&category).unwrap_or_else(|| panic!("'{}' is missing", category));
+            assert!(emoticons.len() >= 10, "'{}' is too small", category);
+        }
+        assert!(get_category("Kaomoji: Table flips").unwrap().contains(&"(╯°□°）╯︵ ┻━┻"));
+        assert!(extra_keywords("(╯°□°）╯︵ ┻━┻").contains(&"flip"));
+        assert!(extra_keywords("😀").is_empty());
+        let keywords = || get_emoticons().iter().flat_map(|(_, e)| e).flat_map(|e| extra_keywords(e));
+        assert!(keywords().all(|k| k.to_lowercase() == *k));
+    }
+
+    #[test]
+    fn test_merge_custom() {
+        let custom: &'static [CustomEmoticon] = Vec::leak(vec![
+            CustomEmoticon::new("(ノ◕ヮ◕)ノ*:･ﾟ✧".to_string(), "classic".to_string(), Vec::new()),
+            CustomEmoticon::new(":-)".to_string(), "Classic".to_string(), Vec::new()),
+            CustomEmoticon::new("🦀".to_string(), "Rust".to_string(), vec!["ferris".to_string()]),
+        ]);
+        let mut categories = get_emoticons().to_vec();
+        merge_custom(&mut categories, custom);
+
+        // Existing categories are extended without duplicates, new ones are appended
+        let (name, classic) = categories.iter().find(|(name, _)| *name == "Classic").unwrap();
+        assert_eq!(*name, "Classic");
+        assert_eq!(classic.len(), get_category("Classic").unwrap().len() + 1);
+        assert_eq!(classic.last().copied(), Some("(ノ◕ヮ◕)ノ*:･ﾟ✧"));
+        assert_eq!(categories.last().unwrap(), &("Rust", vec!["🦀"]));
     }
 
     #[test]
     fn test_filter_by_category() {
         // Test filtering emoticons by category name
-        let filter_text = "happy";
-        let filtered: Vec<&str> = EMOTICONS
-            .keys()
+        let filter_text = "smileys";
+        let filtered: Vec<&str> = get_emoticons()
+            .iter()
+            .map(|(k, _)| *k)
             .filter(|k| k.to_lowercase().contains(&filter_text.to_lowercase()))
-            .copied()
             .collect();
 
-        assert!(filtered.contains(&"Happy"));
+        assert!(filtered.contains(&"Smileys & Emotion"));
     }
 
     #[test]
@@ -95,8 +162,8 @@ mod tests {
         // Test filtering emoticons by emoticon character
         let filter_text = "😀";
         let mut found = false;
-        for (_category, emoticons) in EMOTICONS.iter() {
-            if emoticons.contains(&filter_text.to_string()) {
+        for (_category, emoticons) in get_emoticons().iter() {
+            if emoticons.contains(&filter_text) {
                 found = true;
                 break;
             }
@@ -107,14 +174,157 @@ mod tests {
     #[test]
     fn test_case_insensitive_search() {
         // Test that search is case insensitive
-        let filter_text = "HAPPY";
-        let filtered: Vec<&str> = EMOTICONS
-            .keys()
+        let filter_text = "SMILEYS";
+        let filtered: Vec<&str> = get_emoticons()
+            .iter()
+            .map(|(k, _)| *k)
             .filter(|k| k.to_lowercase().contains(&filter_text.to_lowercase()))
-            .copied()
             .collect();
 
-        assert!(filtered.contains(&"Happy"));
+        assert!(filtered.contains(&"Smileys & Emotion"));
+    }
+
+    #[test]
+    fn test_previously_missing_emoji() {
+        // Emoji that were missing from the old hand-typed list
+        for emoji in ["🐛", "🚀", "🚧", "🇩🇪", "🏳️‍🌈"] {
+            let entry = lookup(emoji).unwrap_or_else(|| panic!("{} is missing", emoji));
+            assert_eq!(entry.status, Qualification::FullyQualified);
+        }
+    }
+
+    #[test]
+    fn test_emoji_metadata() {
+        let entry = lookup("😀").expect("grinning face missing");
+        assert_eq!(entry.name, "grinning face");
+        assert_eq!(entry.group, "Smileys & Emotion");
+        assert_eq!(entry.subgroup, "face-smiling");
+        assert_eq!(entry.codepoints, &[0x1F600]);
+        assert_eq!(entry.version.to_string(), "1.0");
+
+        let entry = lookup("🫠").expect("melting face missing");
+        assert_eq!(entry.version.to_string(), "14.0");
+    }
+
+    #[test]
+    fn test_lookup_index() {
+        // The perfect hash index finds every entry of the table, and nothing else
+        for entry in EMOJI_DATA {
+            assert!(std::ptr::eq(lookup(entry.emoji).unwrap(), entry), "{}", entry.name);
+            assert_eq!(entry.search_name, entry.name.to_lowercase());
+        }
+        assert_eq!(lookup("🇩🇪").map(|e| e.search_name), Some("flag: germany"));
+        assert!(lookup(":-)").is_none());
+        assert!(lookup("😀😀").is_none());
+    }
+
+    #[test]
+    fn test_parse_emoji_version() {
+        assert_eq!("13.0".parse(), Ok(EmojiVersion { major: 13, minor: 0 }));
+        assert_eq!("15.1".parse(), Ok(EmojiVersion { major: 15, minor: 1 }));
+        assert_eq!(" 12 ".parse(), Ok(EmojiVersion { major: 12, minor: 0 }));
+        assert!("".parse::<EmojiVersion>().is_err());
+        assert!("13.x".parse::<EmojiVersion>().is_err());
+        assert!("E13.0".parse::<EmojiVersion>().is_err());
+        assert!("13.0".parse::<EmojiVersion>().unwrap() < "13.1".parse().unwrap());
+    }
+
+    #[test]
+    fn test_qualification_status() {
+        // The unqualified form is known but not offered in the grid
+        assert_eq!(lookup("☺").map(|e| e.status), Some(Qualification::Unqualified));
+        assert_eq!(lookup("☺️").map(|e| e.status), Some(Qualification::FullyQualified));
+        assert_eq!(lookup("🏻").map(|e| e.status), Some(Qualification::Component));
+
+        let offered = get_emoticons().iter().flat_map(|(_, e)| e).any(|e| *e == "☺");
+        assert!(!offered);
+    }
+
+    #[test]
+    fn test_fully_qualified() {
+        assert_eq!(fully_qualified("☺"), Some("☺️"));
+        assert_eq!(fully_qualified("☺️"), Some("☺️"));
+        assert_eq!(fully_qualified("⚡\u{FE0F}"), Some("⚡"));
+        assert_eq!(fully_qualified("🏻"), None);
+        assert_eq!(fully_qualified(":-)"), None);
+    }
+
+    #[test]
+    fn test_codepoints_match_emoji() {
+        for entry in EMOJI_DATA.iter() {
+            let decoded: String = entry.codepoints.iter().filter_map(|&cp| char::from_u32(cp)).collect();
+            assert_eq!(decoded, entry.emoji);
+        }
+    }
+
+    #[test]
+    fn test_skin_tone_variants_not_in_grid() {
+        let offered: Vec<&str> = get_emoticons().iter().flat_map(|(_, e)| e).copied().collect();
+        assert!(offered.contains(&"👍"));
+        assert!(!offered.contains(&"👍🏽"));
+    }
+
+    #[test]
+    fn test_with_skin_tone() {
+        assert_eq!(with_skin_tone("👍", SkinTone::Medium), Some("👍🏽"));
+        assert_eq!(with_skin_tone("👍🏽", SkinTone::None), Some("👍"));
+        assert_eq!(with_skin_tone("🙏", SkinTone::Dark), Some("🙏🏿"));
+
+        // Variation selectors are dropped where the modifier takes their place
+        assert_eq!(with_skin_tone("✌️", SkinTone::Light), Some("✌🏻"));
+        assert_eq!(with_skin_tone("🕵️‍♀️", SkinTone::Light), Some("🕵🏻‍♀️"));
+
+        // Multi-person emoji get the same tone for everyone
+        assert_eq!(skin_tone_of(with_skin_tone("🧑‍🤝‍🧑", SkinTone::Dark).unwrap()), Some(SkinTone::Dark));
+
+        // Emoji without skin tones
+        assert!(!supports_skin_tone("😀"));
+        assert_eq!(with_skin_tone("😀", SkinTone::Medium), None);
+        assert_eq!(with_skin_tone(":-)", SkinTone::Medium), None);
+    }
+
+    #[test]
+    fn test_skin_tone_variants() {
+        assert_eq!(skin_tone_variants("👏"), vec!["👏", "👏🏻", "👏🏼", "👏🏽", "👏🏾", "👏🏿"]);
+        assert_eq!(skin_tone_variants("👏🏾"), skin_tone_variants("👏"));
+        assert!(skin_tone_variants("🚀").is_empty());
+    }
+
+    #[test]
+    fn test_skin_tone_of() {
+        assert_eq!(skin_tone_of("👍"), Some(SkinTone::None));
+        assert_eq!(skin_tone_of("👍🏿"), Some(SkinTone::Dark));
+        assert_eq!(skin_tone_of("🧑🏻‍🤝‍🧑🏿"), None);
+    }
+
+    #[test]
+    fn test_flag_codes() {
+        assert_eq!(flag_code("🇩🇪").as_deref(), Some("DE"));
+        assert_eq!(flag_code("🇺🇳").as_deref(), Some("UN"));
+        assert_eq!(flag_code("🏴󠁧󠁢󠁳󠁣󠁴󠁿").as_deref(), Some("GB-SCT"));
+        assert_eq!(flag_code("🏴"), None);
+        assert_eq!(flag_code("🏁"), None);
+        assert_eq!(flag_code("🇩"), None);
+
+        // Every flag except the handful of plain flags has a code
+        let flags = get_category("Flags").unwrap();
+        let codes: Vec<String> = flags.iter().filter_map(|f| flag_code(f)).collect();
+        assert!(codes.len() >= 250, "only {} flags with codes", codes.len());
+        assert!(codes.contains(&"GB-ENG".to_string()));
+        assert!(codes.contains(&"GB-WLS".to_string()));
+
+        assert_eq!(search_flag_code("🏴󠁧󠁢󠁳󠁣󠁴󠁿"), Some("gb-sct"));
+        assert_eq!(search_flag_code("🏁"), None);
+    }
+
+    #[test]
+    fn test_without_variation_selectors() {
+        use std::borrow::Cow;
+        // Emoji of the table and text without selectors are borrowed
+        assert!(matches!(without_variation_selectors("❤️"), Cow::Borrowed("❤")));
+        assert!(matches!(without_variation_selectors("🏳️‍🌈"), Cow::Borrowed("🏳‍🌈")));
+        assert!(matches!(without_variation_selectors("👍"), Cow::Borrowed("👍")));
+        assert_eq!(without_variation_selectors("⚡\u{FE0F}"), "⚡");
     }
 
     #[test]
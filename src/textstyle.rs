@@ -0,0 +1,191 @@
+//! Text styles for social posts - 𝐛𝐨𝐥𝐝, 𝘪𝘵𝘢𝘭𝘪𝘤, 𝚖𝚘𝚗𝚘, ⓒⓘⓡⓒⓛⓔⓓ, s̶t̶r̶i̶k̶e̶ and upside-down
+//!
+//! Bold, italic and monospace use the Mathematical Alphanumeric Symbols
+//! block, circled letters the Enclosed Alphanumerics. Characters a style
+//! has no form for are kept as they are.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A text style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Italic,
+    Monospace,
+    Circled,
+    Strikethrough,
+    UpsideDown,
+}
+
+impl Style {
+    /// All styles in the order they are offered
+    pub const ALL: [Style; 6] = [
+        Style::Bold,
+        Style::Italic,
+        Style::Monospace,
+        Style::Circled,
+        Style::Strikethrough,
+        Style::UpsideDown,
+    ];
+
+    /// Name on the command line, e.g. "upside-down"
+    pub fn name(self) -> &'static str {
+        match self {
+            Style::Bold => "bold",
+            Style::Italic => "italic",
+            Style::Monospace => "mono",
+            Style::Circled => "circled",
+            Style::Strikethrough => "strike",
+            Style::UpsideDown => "upside-down",
+        }
+    }
+
+    /// Name shown in the picker
+    pub fn label(self) -> &'static str {
+        match self {
+            Style::Bold => "Bold",
+            Style::Italic => "Italic",
+            Style::Monospace => "Monospace",
+            Style::Circled => "Circled",
+            Style::Strikethrough => "Strikethrough",
+            Style::UpsideDown => "Upside down",
+        }
+    }
+
+    /// Write a text in this style
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Style::Bold => text.chars().map(|c| math_char(c, 0x1D400, Some(0x1D7CE))).collect(),
+            Style::Italic => text
+                .chars()
+                .map(|c| match c {
+                    // The italic h was encoded before the block as the Planck constant
+                    'h' => 'ℎ',
+                    c => math_char(c, 0x1D434, None),
+                })
+                .collect(),
+            Style::Monospace => text.chars().map(|c| math_char(c, 0x1D670, Some(0x1D7F6))).collect(),
+            Style::Circled => text.chars().map(circled).collect(),
+            Style::Strikethrough => text
+                .chars()
+                .flat_map(|c| {
+                    let strike = (!c.is_control()).then_some('\u{336}');
+                    std::iter::once(c).chain(strike)
+                })
+                .collect(),
+            Style::UpsideDown => text.chars().rev().map(upside_down).collect(),
+        }
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Style::ALL
+            .into_iter()
+            .find(|style| style.name() == s.to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Style::ALL.iter().map(|style| style.name()).collect();
+                format!("unknown style '{}', expected one of {}", s, names.join(", "))
+            })
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Letter or digit of a Mathematical Alphanumeric Symbols style
+///
+/// `capital_a` is the style's 'A'; the small letters follow the capitals.
+fn math_char(c: char, capital_a: u32, zero: Option<u32>) -> char {
+    let code = match c {
+        'A'..='Z' => capital_a + (c as u32 - 'A' as u32),
+        'a'..='z' => capital_a + 26 + (c as u32 - 'a' as u32),
+        '0'..='9' => match zero {
+            Some(zero) => zero + (c as u32 - '0' as u32),
+            None => return c,
+        },
+        _ => return c,
+    };
+    char::from_u32(code).unwrap_or(c)
+}
+
+fn circled(c: char) -> char {
+    let code = match c {
+        'A'..='Z' => 0x24B6 + (c as u32 - 'A' as u32),
+        'a'..='z' => 0x24D0 + (c as u32 - 'a' as u32),
+        '1'..='9' => 0x2460 + (c as u32 - '1' as u32),
+        '0' => 0x24EA,
+        _ => return c,
+    };
+    char::from_u32(code).unwrap_or(c)
+}
+
+/// Characters that look like the letters turned by 180 degrees
+const UPSIDE_DOWN: &[(char, char)] = &[
+    ('a', 'ɐ'), ('b', 'q'), ('c', 'ɔ'), ('d', 'p'), ('e', 'ǝ'), ('f', 'ɟ'), ('g', 'ƃ'), ('h', 'ɥ'),
+    ('i', 'ᴉ'), ('j', 'ɾ'), ('k', 'ʞ'), ('m', 'ɯ'), ('n', 'u'), ('p', 'd'), ('q', 'b'), ('r', 'ɹ'),
+    ('t', 'ʇ'), ('u', 'n'), ('v', 'ʌ'), ('w', 'ʍ'), ('y', 'ʎ'),
+    ('A', '∀'), ('B', 'ꓭ'), ('C', 'Ɔ'), ('D', 'ᗡ'), ('E', 'Ǝ'), ('F', 'Ⅎ'), ('G', '⅁'), ('J', 'ſ'),
+    ('K', 'ꓘ'), ('L', '˥'), ('M', 'W'), ('P', 'Ԁ'), ('R', 'ꓤ'), ('T', '⊥'), ('U', '∩'), ('V', 'Λ'),
+    ('W', 'M'), ('Y', '⅄'),
+    ('1', 'Ɩ'), ('2', 'ᄅ'), ('3', 'Ɛ'), ('4', 'ㄣ'), ('5', 'ϛ'), ('6', '9'), ('7', 'ㄥ'), ('9', '6'),
+    ('.', '˙'), (',', '\''), ('\'', ','), ('"', '„'), ('?', '¿'), ('!', '¡'), ('&', '⅋'), ('_', '‾'),
+    (';', '؛'), ('(', ')'), (')', '('), ('[', ']'), (']', '['), ('{', '}'), ('}', '{'), ('<', '>'),
+    ('>', '<'),
+];
+
+/// Turn a character, or turn a turned one back
+fn upside_down(c: char) -> char {
+    let turned = UPSIDE_DOWN.iter().find(|(from, _)| *from == c).map(|(_, to)| *to);
+    turned
+        .or_else(|| UPSIDE_DOWN.iter().find(|(_, to)| *to == c).map(|(from, _)| *from))
+        .unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styles() {
+        assert_eq!(Style::Bold.apply("bold 42"), "𝐛𝐨𝐥𝐝 𝟒𝟐");
+        assert_eq!(Style::Italic.apply("italic h"), "𝑖𝑡𝑎𝑙𝑖𝑐 ℎ");
+        assert_eq!(Style::Monospace.apply("Mono 1"), "𝙼𝚘𝚗𝚘 𝟷");
+        assert_eq!(Style::Circled.apply("Circled 10"), "Ⓒⓘⓡⓒⓛⓔⓓ ①⓪");
+        assert_eq!(Style::Strikethrough.apply("strike"), "s̶t̶r̶i̶k̶e̶");
+        assert_eq!(Style::UpsideDown.apply("Hello, world!"), "¡plɹoʍ 'ollǝH");
+    }
+
+    #[test]
+    fn test_other_characters_are_kept() {
+        for style in [Style::Bold, Style::Italic, Style::Monospace, Style::Circled] {
+            assert_eq!(style.apply("äß ✨"), "äß ✨", "{}", style);
+        }
+        // Italic has no digits
+        assert_eq!(Style::Italic.apply("42"), "42");
+        assert_eq!(Style::Strikethrough.apply("a\nb"), "a\u{336}\nb\u{336}");
+    }
+
+    #[test]
+    fn test_upside_down_is_reversible() {
+        let text = "The quick brown fox jumps over the lazy dog (42)!";
+        assert_eq!(Style::UpsideDown.apply(text).chars().count(), text.chars().count());
+        let all = "abcdefghijklmnopqrstuvwxyz ABCDEFGHIJKLMNOPQRSTUVWXYZ 0123456789 .,'\"?!&_;()[]{}<>";
+        assert_eq!(Style::UpsideDown.apply(&Style::UpsideDown.apply(all)), all);
+    }
+
+    #[test]
+    fn test_from_str() {
+        for style in Style::ALL {
+            assert_eq!(style.name().parse(), Ok(style));
+        }
+        assert_eq!("Upside-Down".parse(), Ok(Style::UpsideDown));
+        assert!("fraktur".parse::<Style>().is_err());
+    }
+}
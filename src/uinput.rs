@@ -3,6 +3,7 @@
 //! This module provides direct keyboard input at the kernel level using uinput,
 //! which works on both X11 and Wayland, and even in text consoles.
 
+use crate::settings::PasteShortcut;
 use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
 use std::os::unix::io::AsRawFd;
@@ -24,6 +25,10 @@ const KEY_LEFTSHIFT: u16 = 42;
 const KEY_U: u16 = 22;
 const KEY_ENTER: u16 = 28;
 
+// Keys of the paste shortcuts
+const KEY_V: u16 = 47;
+const KEY_INSERT: u16 = 110;
+
 // Number keys for hex digits
 const KEY_0: u16 = 11;
 const KEY_1: u16 = 2;
@@ -222,49 +227,75 @@ impl UinputKeyboard {
         }
     }
 
+    /// Key events that type a Unicode character with Ctrl+Shift+u, as
+    /// (keycode, value) pairs
+    fn unicode_char_events(c: char) -> Vec<(u16, i32)> {
+        // Press Ctrl+Shift+u to start Unicode input mode, then release u, Shift, and Ctrl
+        let mut events = vec![
+            (KEY_LEFTCTRL, KEY_PRESS),
+            (KEY_LEFTSHIFT, KEY_PRESS),
+            (KEY_U, KEY_PRESS),
+            (KEY_U, KEY_RELEASE),
+            (KEY_LEFTSHIFT, KEY_RELEASE),
+            (KEY_LEFTCTRL, KEY_RELEASE),
+        ];
+
+        // Type each hex digit of the codepoint
+        for hex_char in format!("{:x}", c as u32).chars() {
+            if let Some(keycode) = Self::hex_char_to_keycode(hex_char) {
+                events.push((keycode, KEY_PRESS));
+                events.push((keycode, KEY_RELEASE));
+            }
+        }
+
+        // Press Enter to confirm
+        events.push((KEY_ENTER, KEY_PRESS));
+        events.push((KEY_ENTER, KEY_RELEASE));
+        events
+    }
+
     /// Type a Unicode character using Ctrl+Shift+u method
     /// This is the standard GTK/Linux method for entering Unicode characters
     pub fn type_unicode_char(&mut self, c: char) -> io::Result<()> {
-        // Get Unicode codepoint as hex string
-        let codepoint = c as u32;
-        let hex_string = format!("{:x}", codepoint);
-
-        // Press Ctrl+Shift+u to start Unicode input mode
-        self.press_key(KEY_LEFTCTRL)?;
-        thread::sleep(Duration::from_millis(5));
-        self.press_key(KEY_LEFTSHIFT)?;
-        thread::sleep(Duration::from_millis(5));
-        self.press_key(KEY_U)?;
-        thread::sleep(Duration::from_millis(5));
+        for (keycode, value) in Self::unicode_char_events(c) {
+            self.send_event(EV_KEY, keycode, value)?;
+            self.send_event(EV_SYN, SYN_REPORT, 0)?;
+            // Wait a little longer once the input mode is started
+            let delay = if (keycode, value) == (KEY_LEFTCTRL, KEY_RELEASE) { 10 } else { 5 };
+            thread::sleep(Duration::from_millis(delay));
+        }
+        Ok(())
+    }
 
-        // Release u, Shift, and Ctrl
-        self.release_key(KEY_U)?;
-        thread::sleep(Duration::from_millis(5));
-        self.release_key(KEY_LEFTSHIFT)?;
-        thread::sleep(Duration::from_millis(5));
-        self.release_key(KEY_LEFTCTRL)?;
-        thread::sleep(Duration::from_millis(10));
+    /// Press a paste shortcut like Ctrl+V
+    pub fn paste(&mut self, shortcut: PasteShortcut) -> io::Result<()> {
+        let (modifiers, key): (&[u16], u16) = match shortcut {
+            PasteShortcut::Off => return Ok(()),
+            PasteShortcut::CtrlV => (&[KEY_LEFTCTRL], KEY_V),
+            PasteShortcut::CtrlShiftV => (&[KEY_LEFTCTRL, KEY_LEFTSHIFT], KEY_V),
+            PasteShortcut::ShiftInsert => (&[KEY_LEFTSHIFT], KEY_INSERT),
+        };
 
-        // Type each hex digit
-        for hex_char in hex_string.chars() {
-            if let Some(keycode) = Self::hex_char_to_keycode(hex_char) {
-                self.press_key(keycode)?;
-                thread::sleep(Duration::from_millis(5));
-                self.release_key(keycode)?;
-                thread::sleep(Duration::from_millis(5));
-            }
+        for &modifier in modifiers {
+            self.press_key(modifier)?;
+            thread::sleep(Duration::from_millis(5));
         }
-
-        // Press Enter to confirm
-        self.press_key(KEY_ENTER)?;
+        self.press_key(key)?;
         thread::sleep(Duration::from_millis(5));
-        self.release_key(KEY_ENTER)?;
-        thread::sleep(Duration::from_millis(5));
-
+        self.release_key(key)?;
+        for &modifier in modifiers.iter().rev() {
+            thread::sleep(Duration::from_millis(5));
+            self.release_key(modifier)?;
+        }
         Ok(())
     }
 
     /// Type a string by typing each Unicode character individually
+    ///
+    /// Emoji sequences are typed one code point at a time, including joiners,
+    /// variation selectors, regional indicators and the tag characters of
+    /// subdivision flags, so the receiving application puts the sequence
+    /// back together.
     pub fn type_string(&mut self, text: &str) -> io::Result<()> {
         for c in text.chars() {
             self.type_unicode_char(c)?;
@@ -285,3 +316,55 @@ impl Drop for UinputKeyboard {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hex digit keys typed for each character of a text, checking that
+    /// every character is typed as a complete Ctrl+Shift+u sequence
+    fn typed_digits(text: &str) -> Vec<Vec<u16>> {
+        let start = [
+            (KEY_LEFTCTRL, KEY_PRESS),
+            (KEY_LEFTSHIFT, KEY_PRESS),
+            (KEY_U, KEY_PRESS),
+            (KEY_U, KEY_RELEASE),
+            (KEY_LEFTSHIFT, KEY_RELEASE),
+            (KEY_LEFTCTRL, KEY_RELEASE),
+        ];
+        let confirm = [(KEY_ENTER, KEY_PRESS), (KEY_ENTER, KEY_RELEASE)];
+        text.chars()
+            .map(|c| {
+                let events = UinputKeyboard::unicode_char_events(c);
+                assert!(events.starts_with(&start) && events.ends_with(&confirm), "{:?}", events);
+                let digits = &events[start.len()..events.len() - confirm.len()];
+                assert!(digits.chunks(2).all(|keys| keys == [(keys[0].0, KEY_PRESS), (keys[0].0, KEY_RELEASE)]));
+                digits.iter().step_by(2).map(|&(keycode, _)| keycode).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flag_sequences() {
+        assert_eq!(
+            typed_digits("🇩🇪"),
+            vec![vec![KEY_1, KEY_F, KEY_1, KEY_E, KEY_9], vec![KEY_1, KEY_F, KEY_1, KEY_E, KEY_A]]
+        );
+
+        // Subdivision flags are typed with all of their tag characters
+        let scotland = typed_digits("🏴󠁧󠁢󠁳󠁣󠁴󠁿");
+        assert_eq!(scotland.len(), 7);
+        assert_eq!(scotland[0], vec![KEY_1, KEY_F, KEY_3, KEY_F, KEY_4]);
+        assert_eq!(scotland[1], vec![KEY_E, KEY_0, KEY_0, KEY_6, KEY_7]);
+        assert_eq!(scotland[6], vec![KEY_E, KEY_0, KEY_0, KEY_7, KEY_F]);
+
+        assert_eq!(typed_digits("👩‍💻")[1], vec![KEY_2, KEY_0, KEY_0, KEY_D]);
+    }
+
+    #[test]
+    fn test_every_hex_digit_has_a_key() {
+        for c in "🏴󠁧󠁢󠁷󠁬󠁳󠁿 ❤️ :-)".chars() {
+            let digits = &typed_digits(&c.to_string())[0];
+            assert_eq!(digits.len(), format!("{:x}", c as u32).len(), "{:?}", c);
+        }
+    }
+}